@@ -25,19 +25,31 @@ impl LedgerDataTransformer for StatusListUpdateLedgerData {
     where
         Self: Sized,
     {
-        let accumulator_bytes = bytes[..PointG2::BYTES_REPR_SIZE].to_vec();
-        let rev_list_bytes = bytes[PointG2::BYTES_REPR_SIZE..].to_vec();
-        let accumulator = PointG2::from_bytes(&accumulator_bytes).unwrap();
-        let rev_list = bytes_to_bitvec(rev_list_bytes);
+        Self::try_from_ledger_bytes(bytes).unwrap()
+    }
+}
 
-        Self {
+impl StatusListUpdateLedgerData {
+    /// Like [LedgerDataTransformer::from_ledger_bytes], but fails on malformed ledger data.
+    pub fn try_from_ledger_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < PointG2::BYTES_REPR_SIZE {
+            return Err(format!(
+                "expected an accumulator of {} bytes, got {} bytes",
+                PointG2::BYTES_REPR_SIZE,
+                bytes.len()
+            ));
+        }
+        let (accumulator_bytes, rev_list_bytes) = bytes.split_at(PointG2::BYTES_REPR_SIZE);
+        let accumulator = PointG2::from_bytes(accumulator_bytes)
+            .map_err(|e| format!("invalid accumulator: {e:?}"))?;
+        let rev_list = bytes_to_bitvec(rev_list_bytes.to_vec());
+
+        Ok(Self {
             accumulator,
             rev_list,
-        }
+        })
     }
-}
 
-impl StatusListUpdateLedgerData {
     pub fn from_anoncreds_data(anoncreds_data: &anoncreds::types::RevocationStatusList) -> Self {
         // dismantle the inner parts that we can't access
         let revocation_status_list_json: Value = serde_json::to_value(anoncreds_data).unwrap();
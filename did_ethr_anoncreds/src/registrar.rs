@@ -6,7 +6,10 @@ use anoncreds::{
 };
 use did_ethr_linked_resources::{
     config::ContractNetworkConfig,
-    registrar::EthrDidLinkedResourcesRegistrar,
+    content_encoding::ContentEncoding,
+    registrar::{CreateResourceOptions, EthrDidLinkedResourcesRegistrar},
+    resource_content::JSON_MEDIA_TYPE,
+    telemetry::HttpProvider,
     types::{input::ResourceInput, output::Resource},
 };
 use ethers::providers::Middleware;
@@ -17,8 +20,8 @@ use super::ledger_data_transformer::{
     SCHEMA_RESOURCE_TYPE, STATUS_LIST_RESOURCE_TYPE,
 };
 
-pub struct EthrDidAnoncredsRegistrar<S> {
-    dlr_registrar: EthrDidLinkedResourcesRegistrar<S>,
+pub struct EthrDidAnoncredsRegistrar<S, M = HttpProvider> {
+    dlr_registrar: EthrDidLinkedResourcesRegistrar<S, M>,
 }
 
 impl<S> EthrDidAnoncredsRegistrar<S>
@@ -31,26 +34,63 @@ where
         }
     }
 
+    /// Create a registrar which writes anoncreds objects using a delegate of the issuer DID
+    /// (of type `delegate_type`), rather than the DID controller.
+    pub fn new_as_delegate(
+        signer: Arc<S>,
+        dlr_config: ContractNetworkConfig,
+        delegate_type: &str,
+    ) -> Self {
+        Self {
+            dlr_registrar: EthrDidLinkedResourcesRegistrar::new_as_delegate(
+                signer,
+                dlr_config,
+                delegate_type,
+            ),
+        }
+    }
+}
+
+impl<S, M> EthrDidAnoncredsRegistrar<S, M>
+where
+    S: Middleware,
+    M: Middleware + 'static,
+{
+    /// Create a registrar which reads with the given `client` (e.g. a
+    /// `did_ethr_linked_resources::mock::MockLedger` provider), rather than a HTTP provider for
+    /// the `dlr_config.rpc_url`.
+    pub fn with_client(signer: Arc<S>, dlr_config: ContractNetworkConfig, client: Arc<M>) -> Self {
+        Self {
+            dlr_registrar: EthrDidLinkedResourcesRegistrar::with_client(signer, dlr_config, client),
+        }
+    }
+
     pub fn change_signer(&mut self, new_signer: Arc<S>) {
         self.dlr_registrar.change_signer(new_signer);
     }
 
+    /// Write the `schema`, unless an identical schema (same name, version and attributes) is
+    /// already the latest version, in which case that is returned.
+    #[tracing::instrument(skip(self, schema))]
     pub async fn write_schema(&self, issuer_did: &str, schema: Schema) -> Resource {
         self.dlr_registrar
-            .create_resource(
+            .create_resource_with_options(
                 &issuer_did,
                 ResourceInput {
                     resource_name: schema.name.clone(),
                     resource_type: SCHEMA_RESOURCE_TYPE.to_owned(),
                     resource_version_id: schema.version.clone(),
-                    media_type: BINARY_MEDIA_TYPE.to_owned(),
+                    media_type: JSON_MEDIA_TYPE.to_owned(),
                     content: LedgerDataTransformer::into_ledger_bytes(schema),
+                    content_encoding: None,
                 },
+                &CreateResourceOptions { if_changed: true },
             )
             .await
             .unwrap()
     }
 
+    #[tracing::instrument(skip(self, cred_def))]
     pub async fn write_cred_def(
         &self,
         issuer_did: &str,
@@ -63,14 +103,17 @@ where
                     resource_name: cred_def.tag.clone(),
                     resource_type: CRED_DEF_RESOURCE_TYPE.to_owned(),
                     resource_version_id: NO_VERSION.to_owned(),
-                    media_type: BINARY_MEDIA_TYPE.to_owned(),
+                    media_type: JSON_MEDIA_TYPE.to_owned(),
                     content: LedgerDataTransformer::into_ledger_bytes(cred_def),
+                    // cred defs are large, compress them to reduce the cost of writing
+                    content_encoding: Some(ContentEncoding::Zstd),
                 },
             )
             .await
             .unwrap()
     }
 
+    #[tracing::instrument(skip(self, rev_reg_def))]
     pub async fn write_rev_reg_def(
         &self,
         issuer_did: &str,
@@ -83,14 +126,16 @@ where
                     resource_name: rev_reg_def.tag.clone(),
                     resource_type: REV_REG_DEF_RESOURCE_TYPE.to_owned(),
                     resource_version_id: NO_VERSION.to_owned(),
-                    media_type: BINARY_MEDIA_TYPE.to_owned(),
+                    media_type: JSON_MEDIA_TYPE.to_owned(),
                     content: LedgerDataTransformer::into_ledger_bytes(rev_reg_def),
+                    content_encoding: None,
                 },
             )
             .await
             .unwrap()
     }
 
+    #[tracing::instrument(skip(self, rev_list))]
     pub async fn write_rev_status_list(
         &self,
         issuer_did: &str,
@@ -108,6 +153,7 @@ where
                     content: LedgerDataTransformer::into_ledger_bytes(
                         StatusListUpdateLedgerData::from_anoncreds_data(&rev_list),
                     ),
+                    content_encoding: None,
                 },
             )
             .await
@@ -1,78 +1,136 @@
+use std::{error::Error, sync::Arc};
+
 use anoncreds::{
     data_types::{cred_def::CredentialDefinition, schema::Schema},
     types::{RevocationRegistryDefinition, RevocationStatusList},
 };
 use chrono::{TimeZone, Utc};
 use did_ethr_linked_resources::{
-    config::ContractNetworkConfig, resolver::EthrDidLinkedResourcesResolver,
+    config::ContractNetworkConfig,
+    error::ContentDecodeError,
+    resolver::EthrDidLinkedResourcesResolver,
+    resource_content::{ContentDecoders, ResourceContent},
+    telemetry::HttpProvider,
+    types::{output::Resource, query::ResourceQuery},
 };
-
-use crate::ledger_data_transformer::LedgerDataTransformer;
+use ethers::providers::Middleware;
+use serde::de::DeserializeOwned;
 
 use super::ledger_data_transformer::{
-    status_list_update_ledger_data::StatusListUpdateLedgerData, STATUS_LIST_RESOURCE_TYPE,
+    status_list_update_ledger_data::StatusListUpdateLedgerData, BINARY_MEDIA_TYPE,
+    STATUS_LIST_RESOURCE_TYPE,
 };
 
-pub struct EthrDidAnoncredsResolver {
-    dlr_resolver: EthrDidLinkedResourcesResolver,
+pub struct EthrDidAnoncredsResolver<M = HttpProvider> {
+    dlr_resolver: EthrDidLinkedResourcesResolver<M>,
+    content_decoders: ContentDecoders,
 }
 
 impl EthrDidAnoncredsResolver {
     pub fn new(dlr_config: ContractNetworkConfig) -> Self {
         Self {
             dlr_resolver: EthrDidLinkedResourcesResolver::new(dlr_config),
+            content_decoders: anoncreds_content_decoders(),
+        }
+    }
+}
+
+impl<M: Middleware + 'static> EthrDidAnoncredsResolver<M> {
+    /// Resolve with the given `client` (e.g. a `did_ethr_linked_resources::mock::MockLedger`
+    /// provider), rather than a HTTP provider for the `dlr_config.rpc_url`.
+    pub fn with_client(dlr_config: ContractNetworkConfig, client: Arc<M>) -> Self {
+        Self {
+            dlr_resolver: EthrDidLinkedResourcesResolver::with_client(dlr_config, client),
+            content_decoders: anoncreds_content_decoders(),
         }
     }
 
-    pub async fn fetch_schema(&self, schema_id: &str) -> Schema {
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_schema(&self, schema_id: &str) -> Result<Schema, Box<dyn Error>> {
         // fetch schema from ledger
-        println!("Fetching schema {schema_id}...");
-        let resource = self.dlr_resolver.resolve_query(schema_id).await.unwrap();
-        LedgerDataTransformer::from_ledger_bytes(&resource.content)
+        let resource = self.dlr_resolver.resolve_query(schema_id).await?;
+        Ok(decode_json_primitive(&resource)?)
     }
 
-    pub async fn fetch_cred_def(&self, cred_def_id: &str) -> CredentialDefinition {
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_cred_def(
+        &self,
+        cred_def_id: &str,
+    ) -> Result<CredentialDefinition, Box<dyn Error>> {
         // fetch cred def from ledger
-        println!("Fetching cred def {cred_def_id}...");
-        let resource = self.dlr_resolver.resolve_query(cred_def_id).await.unwrap();
-        LedgerDataTransformer::from_ledger_bytes(&resource.content)
+        let resource = self.dlr_resolver.resolve_query(cred_def_id).await?;
+        Ok(decode_json_primitive(&resource)?)
     }
 
-    pub async fn fetch_rev_reg_def(&self, rev_reg_def_id: &str) -> RevocationRegistryDefinition {
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_rev_reg_def(
+        &self,
+        rev_reg_def_id: &str,
+    ) -> Result<RevocationRegistryDefinition, Box<dyn Error>> {
         // fetch rev reg def from ledger
-        println!("Fetching rev reg def {rev_reg_def_id}...");
-        let resource = self
-            .dlr_resolver
-            .resolve_query(rev_reg_def_id)
-            .await
-            .unwrap();
-        LedgerDataTransformer::from_ledger_bytes(&resource.content)
+        let resource = self.dlr_resolver.resolve_query(rev_reg_def_id).await?;
+        Ok(decode_json_primitive(&resource)?)
     }
 
+    #[tracing::instrument(skip(self, rev_reg_def))]
     pub async fn fetch_rev_status_list_as_of_timestamp(
         &self,
         rev_reg_id: &str,
         rev_reg_def: &RevocationRegistryDefinition,
         timestamp: u64,
-    ) -> (RevocationStatusList, u64) {
+    ) -> Result<(RevocationStatusList, u64), Box<dyn Error>> {
         let issuer_did = &rev_reg_def.issuer_id.0;
         let rev_reg_name = &rev_reg_def.tag;
-        let version_time = Utc.timestamp_opt(timestamp as i64, 0).unwrap().to_rfc3339(); // TODO - does this have Z?
-        let version_time_url = urlencoding::encode(&version_time);
+        let version_time = Utc
+            .timestamp_opt(timestamp as i64, 0)
+            .single()
+            .ok_or("Invalid timestamp")?;
 
         // https://docs.cheqd.io/identity/advanced/anoncreds/revocation-status-list#obtain-status-list-content-at-a-point-in-time
         // did:cheqd:mainnet:zF7rhDBfUt9d1gJPjx7s1J?universityDegree&resourceType=anonCredsStatusList&versionTime=2022-08-21T08:40:00Z
         // NOTE ^ i think above is missing resourceName=universityDegree
-        let query = format!("{issuer_did}?resourceName={rev_reg_name}&resourceType={STATUS_LIST_RESOURCE_TYPE}&versionTime={version_time_url}");
-        println!("Fetching status list with query: {query}");
-        let resource = self.dlr_resolver.resolve_query(&query).await.unwrap();
+        let query = ResourceQuery::builder(issuer_did)?
+            .resource_name(rev_reg_name)
+            .resource_type(STATUS_LIST_RESOURCE_TYPE)
+            .version_time(version_time)
+            .build()
+            .to_did_url();
+        tracing::debug!(query, "fetching status list");
+        let resource = self.dlr_resolver.resolve_query(&query).await?;
 
         let resource_timestamp = resource.metadata.created.timestamp() as u64;
-        let rev_list_ledger_data: StatusListUpdateLedgerData =
-            LedgerDataTransformer::from_ledger_bytes(&resource.content);
+        let content = resource.decode_content(&self.content_decoders)?;
+        let rev_list_ledger_data = content
+            .downcast_ref::<StatusListUpdateLedgerData>()
+            .ok_or("Status list resource was not decoded as a status list")?
+            .clone();
 
         let rev_list = rev_list_ledger_data.into_anoncreds_data(resource_timestamp, rev_reg_id);
 
-        (rev_list, resource_timestamp)
+        Ok((rev_list, resource_timestamp))
+    }
+}
+
+/// Decoders of the content of anoncreds resources: status lists are decoded (as
+/// [StatusListUpdateLedgerData]) from their ledger bytes.
+fn anoncreds_content_decoders() -> ContentDecoders {
+    let mut decoders = ContentDecoders::default();
+    decoders.register_resource_type(STATUS_LIST_RESOURCE_TYPE, |content| {
+        let ledger_data = StatusListUpdateLedgerData::try_from_ledger_bytes(content)
+            .map_err(ContentDecodeError::Malformed)?;
+        Ok(ResourceContent::custom(ledger_data))
+    });
+    decoders
+}
+
+/// Decode an anoncreds primitive written as JSON, or as (JSON) bytes, as it was before
+/// primitives were written with a JSON media type.
+fn decode_json_primitive<T: DeserializeOwned>(
+    resource: &Resource,
+) -> Result<T, ContentDecodeError> {
+    if resource.metadata.media_type == BINARY_MEDIA_TYPE {
+        return serde_json::from_slice(&resource.content)
+            .map_err(|e| ContentDecodeError::Malformed(e.to_string()));
     }
+    resource.decode()
 }
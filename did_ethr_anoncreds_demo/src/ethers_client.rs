@@ -1,16 +1,16 @@
 use std::{env, sync::Arc};
 
+use did_ethr_linked_resources::telemetry::{self, HttpProvider};
 use dotenv::dotenv;
 use ethers::{
     core::k256::ecdsa::SigningKey,
     middleware::SignerMiddleware,
-    providers::{Http, Provider},
     signers::{coins_bip39::English, MnemonicBuilder, Signer, Wallet},
 };
 
 use crate::config::DemoConfig;
 
-pub type EtherSigner = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
+pub type EtherSigner = SignerMiddleware<HttpProvider, Wallet<SigningKey>>;
 
 pub fn get_writer_ethers_client(id: u32, config: &DemoConfig) -> Arc<EtherSigner> {
     dotenv().ok();
@@ -25,6 +25,6 @@ pub fn get_writer_ethers_client(id: u32, config: &DemoConfig) -> Arc<EtherSigner
         .unwrap()
         .with_chain_id(config.chain_id);
 
-    let provider = Provider::<Http>::try_from(&config.rpc_url).unwrap();
+    let provider = telemetry::http_provider(&config.rpc_url).unwrap();
     Arc::new(SignerMiddleware::new(provider, wallet))
 }
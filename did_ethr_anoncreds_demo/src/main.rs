@@ -7,6 +7,7 @@ pub mod utils;
 use did_ethr_linked_resources::utils::did_identity_as_full_did;
 use serde_json::json;
 use std::{
+    error::Error,
     io::{self, BufRead, Write},
     time::Duration,
 };
@@ -113,7 +114,7 @@ async fn did_controller_auth_demo(issuer: &mut Issuer, conf: &DemoConfig) {
     let res = issuer
         .write_arbitrary_resource(JsonLedgerData(resource.clone()))
         .await;
-    println!("success: {}", res.is_ok());
+    print_write_result(&res);
 
     // change controller and write with new controller
     let new_controller = get_writer_ethers_client(1, conf);
@@ -134,7 +135,7 @@ async fn did_controller_auth_demo(issuer: &mut Issuer, conf: &DemoConfig) {
     let res = issuer
         .write_arbitrary_resource(JsonLedgerData(resource.clone()))
         .await;
-    println!("success: {}", res.is_ok());
+    print_write_result(&res);
 
     // try writing with the incorrect controller (new random controller)
     let wrong_controller = get_writer_ethers_client(2, conf);
@@ -147,7 +148,7 @@ async fn did_controller_auth_demo(issuer: &mut Issuer, conf: &DemoConfig) {
     let res = issuer
         .write_arbitrary_resource(JsonLedgerData(resource.clone()))
         .await;
-    println!("success: {}", res.is_ok());
+    print_write_result(&res);
 
     // try writing with incorrect controller (original controller)
     println!(
@@ -158,7 +159,7 @@ async fn did_controller_auth_demo(issuer: &mut Issuer, conf: &DemoConfig) {
     let res = issuer
         .write_arbitrary_resource(JsonLedgerData(resource))
         .await;
-    println!("success: {}", res.is_ok());
+    print_write_result(&res);
 
     // change back to original for sake of the new
     println!(
@@ -172,6 +173,13 @@ async fn did_controller_auth_demo(issuer: &mut Issuer, conf: &DemoConfig) {
     println!("\n########## END OF AUTH DEMO ###########\n");
 }
 
+fn print_write_result(res: &Result<(), Box<dyn Error>>) {
+    match res {
+        Ok(()) => println!("success: true"),
+        Err(e) => println!("success: false, error: {e}"),
+    }
+}
+
 /// Run thru a single credential issuance flow. Issuing a revocable credential.
 async fn issuance_demo(holder: &mut Holder, issuer: &mut Issuer) {
     println!("\n########## ISSUANCE ###########\n");
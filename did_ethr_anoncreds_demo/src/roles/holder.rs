@@ -46,7 +46,8 @@ impl Holder {
         let fetched_cred_def = self
             .anoncreds_resolver
             .fetch_cred_def(&cred_offer.cred_def_id.0)
-            .await;
+            .await
+            .unwrap();
 
         let (cred_request, cred_request_metadata) = anoncreds::prover::create_credential_request(
             Some("entropy"),
@@ -67,11 +68,13 @@ impl Holder {
         let fetched_cred_def = self
             .anoncreds_resolver
             .fetch_cred_def(&credential.cred_def_id.0)
-            .await;
+            .await
+            .unwrap();
         let fetched_rev_reg_def = self
             .anoncreds_resolver
             .fetch_rev_reg_def(&credential.rev_reg_id.as_ref().unwrap().0)
-            .await;
+            .await
+            .unwrap();
 
         anoncreds::prover::process_credential(
             &mut credential,
@@ -100,7 +103,8 @@ impl Holder {
         let schema_for_cred = self
             .anoncreds_resolver
             .fetch_schema(&holder_cred.schema_id.0)
-            .await;
+            .await
+            .unwrap();
         schemas.insert(&holder_cred.schema_id, &schema_for_cred);
 
         // construct cred defs
@@ -108,7 +112,8 @@ impl Holder {
         let cred_def_for_cred = self
             .anoncreds_resolver
             .fetch_cred_def(&holder_cred.cred_def_id.0)
-            .await;
+            .await
+            .unwrap();
         cred_defs.insert(&holder_cred.cred_def_id, &cred_def_for_cred);
 
         // specify creds to use for referents
@@ -138,7 +143,8 @@ impl Holder {
         let schema_for_cred = self
             .anoncreds_resolver
             .fetch_schema(&holder_cred.schema_id.0)
-            .await;
+            .await
+            .unwrap();
         schemas.insert(&holder_cred.schema_id, &schema_for_cred);
 
         // construct cred defs
@@ -146,12 +152,17 @@ impl Holder {
         let cred_def_for_cred = self
             .anoncreds_resolver
             .fetch_cred_def(&holder_cred.cred_def_id.0)
-            .await;
+            .await
+            .unwrap();
         cred_defs.insert(&holder_cred.cred_def_id, &cred_def_for_cred);
 
         // construct rev_state
         let rev_reg_id = &holder_cred.rev_reg_id.as_ref().unwrap().0;
-        let rev_reg_def = self.anoncreds_resolver.fetch_rev_reg_def(rev_reg_id).await;
+        let rev_reg_def = self
+            .anoncreds_resolver
+            .fetch_rev_reg_def(rev_reg_id)
+            .await
+            .unwrap();
         let requested_nrp_timestamp = presentation_request
             .value()
             .non_revoked
@@ -166,7 +177,8 @@ impl Holder {
                 &rev_reg_def,
                 requested_nrp_timestamp,
             )
-            .await;
+            .await
+            .unwrap();
 
         let rev_reg_idx = holder_cred.signature.extract_index().unwrap();
         let rev_state = create_or_update_revocation_state(
@@ -217,6 +217,7 @@ impl Issuer {
                     resource_version_id: String::new(),
                     media_type: random_id(),
                     content: LedgerDataTransformer::into_ledger_bytes(resource),
+                    content_encoding: None,
                 },
             )
             .await?;
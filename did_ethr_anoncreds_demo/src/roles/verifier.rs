@@ -93,12 +93,20 @@ impl Verifier {
 
         // construct schemas
         let mut schemas: HashMap<&SchemaId, &Schema> = HashMap::new();
-        let schema_for_cred = self.anoncreds_resolver.fetch_schema(&schema_id.0).await;
+        let schema_for_cred = self
+            .anoncreds_resolver
+            .fetch_schema(&schema_id.0)
+            .await
+            .unwrap();
         schemas.insert(&schema_id, &schema_for_cred);
 
         // construct cred defs
         let mut cred_defs: HashMap<&CredentialDefinitionId, &CredentialDefinition> = HashMap::new();
-        let cred_def_for_cred = self.anoncreds_resolver.fetch_cred_def(&cred_def_id.0).await;
+        let cred_def_for_cred = self
+            .anoncreds_resolver
+            .fetch_cred_def(&cred_def_id.0)
+            .await
+            .unwrap();
         cred_defs.insert(&cred_def_id, &cred_def_for_cred);
 
         anoncreds::verifier::verify_presentation(
@@ -122,16 +130,28 @@ impl Verifier {
 
         // construct schemas
         let mut schemas: HashMap<&SchemaId, &Schema> = HashMap::new();
-        let schema_for_cred = self.anoncreds_resolver.fetch_schema(&schema_id.0).await;
+        let schema_for_cred = self
+            .anoncreds_resolver
+            .fetch_schema(&schema_id.0)
+            .await
+            .unwrap();
         schemas.insert(&schema_id, &schema_for_cred);
 
         // construct cred defs
         let mut cred_defs: HashMap<&CredentialDefinitionId, &CredentialDefinition> = HashMap::new();
-        let cred_def_for_cred = self.anoncreds_resolver.fetch_cred_def(&cred_def_id.0).await;
+        let cred_def_for_cred = self
+            .anoncreds_resolver
+            .fetch_cred_def(&cred_def_id.0)
+            .await
+            .unwrap();
         cred_defs.insert(&cred_def_id, &cred_def_for_cred);
 
         // construct rev reg def
-        let rev_reg_def_for_cred = self.anoncreds_resolver.fetch_rev_reg_def(&rev_reg_id).await;
+        let rev_reg_def_for_cred = self
+            .anoncreds_resolver
+            .fetch_rev_reg_def(&rev_reg_id)
+            .await
+            .unwrap();
 
         // construct rev info
         let (rev_status_list, _update_timestamp) = self
@@ -141,7 +161,8 @@ impl Verifier {
                 &rev_reg_def_for_cred,
                 presented_timestamp,
             )
-            .await;
+            .await
+            .unwrap();
 
         let mut rev_reg_defs: HashMap<
             &RevocationRegistryDefinitionId,
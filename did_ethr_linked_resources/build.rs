@@ -35,4 +35,38 @@ fn main() {
         .unwrap()
         .write_to_file(out_file)
         .unwrap();
+
+    // gen types for the views of version 1 EthrDIDLinkedResourcesRegistry deployments (whose
+    // resources are keyed differently), from the checked in ABI
+
+    let abi_source = "./abi/EthrDIDLinkedResourcesRegistryV1.json";
+    let out_file = Path::new(&out_dir).join("ethr_dlr_registry_v1_contract.rs");
+    if out_file.exists() {
+        std::fs::remove_file(&out_file).unwrap();
+    }
+
+    Abigen::new("EthrDLRRegistryV1", abi_source)
+        .unwrap()
+        .generate()
+        .unwrap()
+        .write_to_file(out_file)
+        .unwrap();
+
+    // gen types (with the bytecode) for the version 1 EthrDIDLinkedResourcesRegistry, which the
+    // `anvil` harness deploys to test reading version 1 deployments
+
+    if env::var_os("CARGO_FEATURE_ANVIL").is_some() {
+        let abi_source = "../smart-contracts/artifacts/contracts/legacy/EthrDIDLinkedResourcesRegistryV1.sol/EthrDIDLinkedResourcesRegistryV1.json";
+        let out_file = Path::new(&out_dir).join("ethr_dlr_registry_v1_deployment_contract.rs");
+        if out_file.exists() {
+            std::fs::remove_file(&out_file).unwrap();
+        }
+
+        Abigen::new("EthrDLRRegistryV1", abi_source)
+            .unwrap()
+            .generate()
+            .unwrap()
+            .write_to_file(out_file)
+            .unwrap();
+    }
 }
@@ -0,0 +1,143 @@
+//! A local chain for integration tests (with the `anvil` feature), so that the real contracts can
+//! be tested end to end in plain `cargo test`, without starting a node and deploying by hand.
+//!
+//! [AnvilNetwork::start] spawns a local [Anvil](https://book.getfoundry.sh/anvil/) node on a free
+//! port, and deploys the `EthereumDIDRegistry` and `EthrDIDLinkedResourcesRegistry` to it from
+//! their compiled (Hardhat artifact) bytecode, as `scripts/deploy.ts` does.
+//!
+//! ```ignore
+//! let network = AnvilNetwork::start().await?;
+//!
+//! let signer = network.signer(0);
+//! let did = did_identity_as_full_did(&signer.address(), network.chain_id());
+//! let registrar = EthrDidLinkedResourcesRegistrar::new(signer, network.dlr_network_config());
+//! let resolver = EthrDidLinkedResourcesResolver::new(network.dlr_network_config());
+//! ```
+//!
+//! [AnvilNetwork::deploy_v1_dlr_registry] also deploys a version 1 registry (from
+//! `contracts/legacy`), to test that version 1 deployments can still be read.
+//!
+//! The `anvil` binary must be on the `PATH` (see the [Foundry](https://getfoundry.sh) install).
+//! The node is killed when the [AnvilNetwork] is dropped.
+
+use std::{error::Error, sync::Arc, time::Duration};
+
+use ethers::{
+    middleware::SignerMiddleware,
+    signers::{LocalWallet, Signer},
+    types::Address,
+    utils::{Anvil, AnvilInstance},
+};
+
+use self::v1_registry::EthrDLRRegistryV1;
+use crate::{
+    config::ContractNetworkConfig,
+    contracts::{eth_did_registry::EthereumDIDRegistry, ethr_dlr_registry::EthrDLRRegistry},
+    telemetry::{self, HttpProvider},
+};
+
+/// Signer of a funded account of an [AnvilNetwork].
+pub type AnvilSigner = SignerMiddleware<HttpProvider, LocalWallet>;
+
+/// Bindings of the version 1 `EthrDIDLinkedResourcesRegistry` (see
+/// [AnvilNetwork::deploy_v1_dlr_registry]).
+pub mod v1_registry {
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/ethr_dlr_registry_v1_deployment_contract.rs"
+    ));
+}
+
+/// A local Anvil node with both registries deployed.
+pub struct AnvilNetwork {
+    anvil: AnvilInstance,
+    did_registry_address: Address,
+    dlr_registry_address: Address,
+}
+
+impl AnvilNetwork {
+    /// Spawn a node and deploy both registries to it, from the first account.
+    ///
+    /// Panics if the `anvil` binary can't be spawned.
+    pub async fn start() -> Result<Self, Box<dyn Error>> {
+        let anvil = Anvil::new().spawn();
+
+        let deployer = signer_of(&anvil, 0)?;
+        let did_registry = EthereumDIDRegistry::deploy(deployer.clone(), ())?
+            .send()
+            .await?;
+        let dlr_registry = EthrDLRRegistry::deploy(deployer, did_registry.address())?
+            .send()
+            .await?;
+
+        Ok(Self {
+            did_registry_address: did_registry.address(),
+            dlr_registry_address: dlr_registry.address(),
+            anvil,
+        })
+    }
+
+    /// Deploy a version 1 `EthrDIDLinkedResourcesRegistry` (as first deployed, before
+    /// `REGISTRY_VERSION`) from the first account, to test reading older deployments.
+    pub async fn deploy_v1_dlr_registry(&self) -> Result<Address, Box<dyn Error>> {
+        let dlr_registry = EthrDLRRegistryV1::deploy(self.signer(0), self.did_registry_address)?
+            .send()
+            .await?;
+        Ok(dlr_registry.address())
+    }
+
+    pub fn rpc_url(&self) -> String {
+        self.anvil.endpoint()
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.anvil.chain_id()
+    }
+
+    pub fn did_registry_address(&self) -> Address {
+        self.did_registry_address
+    }
+
+    pub fn dlr_registry_address(&self) -> Address {
+        self.dlr_registry_address
+    }
+
+    /// Config of the deployed `EthrDIDLinkedResourcesRegistry`.
+    pub fn dlr_network_config(&self) -> ContractNetworkConfig {
+        self.network_config(self.dlr_registry_address)
+    }
+
+    /// Config of the deployed `EthereumDIDRegistry`.
+    pub fn did_ethr_network_config(&self) -> ContractNetworkConfig {
+        self.network_config(self.did_registry_address)
+    }
+
+    /// Number of funded accounts.
+    pub fn accounts(&self) -> usize {
+        self.anvil.keys().len()
+    }
+
+    /// Signer of the funded account at `index` (of the default `test test .. junk` mnemonic).
+    ///
+    /// Panics if the index is not below [Self::accounts].
+    pub fn signer(&self, index: usize) -> Arc<AnvilSigner> {
+        signer_of(&self.anvil, index).unwrap()
+    }
+
+    fn network_config(&self, contract_address: Address) -> ContractNetworkConfig {
+        ContractNetworkConfig {
+            rpc_url: self.rpc_url(),
+            contract_address: format!("{contract_address:?}"),
+            chain_id: self.chain_id(),
+        }
+    }
+}
+
+fn signer_of(anvil: &AnvilInstance, index: usize) -> Result<Arc<AnvilSigner>, Box<dyn Error>> {
+    let key = anvil.keys().get(index).ok_or("no such anvil account")?;
+    let wallet = LocalWallet::from(key.clone()).with_chain_id(anvil.chain_id());
+
+    // anvil mines as soon as a transaction arrives
+    let provider = telemetry::http_provider(&anvil.endpoint())?.interval(Duration::from_millis(10));
+    Ok(Arc::new(SignerMiddleware::new(provider, wallet)))
+}
@@ -0,0 +1,156 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{content_store::content_hash, types::input::ResourceInput};
+
+/// Media type of resources whose on-chain content is a [ChunkManifest], rather than the content
+/// itself.
+pub const CHUNK_MANIFEST_MEDIA_TYPE: &str = "application/vnd.ethr-dlr.chunk-manifest+json";
+
+/// Media type of the chunk resources referenced by a [ChunkManifest].
+pub const CHUNK_MEDIA_TYPE: &str = "application/vnd.ethr-dlr.chunk";
+
+/// Appended to the resource type of a chunked resource, to give the resource type of its chunks.
+/// This keeps chunks out of the version history of the resource itself.
+pub const CHUNK_RESOURCE_TYPE_SUFFIX: &str = "#chunk";
+
+/// Maximum number of chunks in a [ChunkManifest]. Manifests are written by third parties, so
+/// this bounds the number of chunks fetched to resolve one.
+pub const MAX_CHUNKS: usize = 1024;
+
+/// The on-chain content of a resource which was uploaded in chunks (each chunk being a resource
+/// of its own, written in a separate transaction).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkManifest {
+    /// resource IDs of the chunks, in order
+    pub chunk_ids: Vec<String>,
+    /// hex encoded keccak256 hash of the assembled content, verified when the content is assembled
+    pub content_hash: String,
+    /// media type of the assembled content
+    pub media_type: String,
+}
+
+/// Progress of a chunked upload, see
+/// [crate::registrar::EthrDidLinkedResourcesRegistrar::create_resource_chunked].
+///
+/// If an upload fails part way, this can be persisted and passed back to resume the upload from
+/// the first chunk which was not written.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkedUpload {
+    /// hex encoded keccak256 hash of the content being uploaded
+    pub content_hash: String,
+    pub total_chunks: usize,
+    /// resource IDs of the chunks written so far, in order
+    pub written_chunk_ids: Vec<String>,
+}
+
+impl ChunkedUpload {
+    pub fn is_complete(&self) -> bool {
+        self.total_chunks > 0 && self.written_chunk_ids.len() == self.total_chunks
+    }
+}
+
+/// split the (ledger ready) `resource_input` into chunk resource inputs of at most `chunk_size`
+/// bytes of content
+pub(crate) fn chunk_resource_inputs(
+    resource_input: &ResourceInput,
+    chunk_size: usize,
+) -> Vec<ResourceInput> {
+    resource_input
+        .content
+        .chunks(chunk_size)
+        .map(|chunk| ResourceInput {
+            resource_name: resource_input.resource_name.clone(),
+            resource_type: format!(
+                "{}{CHUNK_RESOURCE_TYPE_SUFFIX}",
+                resource_input.resource_type
+            ),
+            resource_version_id: String::new(),
+            media_type: CHUNK_MEDIA_TYPE.to_owned(),
+            content: chunk.to_vec(),
+            content_encoding: None,
+        })
+        .collect()
+}
+
+/// the manifest resource input to write (in place of `resource_input`) once its chunks are
+/// written as `chunk_ids`
+pub(crate) fn manifest_resource_input(
+    resource_input: ResourceInput,
+    chunk_ids: Vec<String>,
+) -> Result<ResourceInput, Box<dyn Error>> {
+    let manifest = ChunkManifest {
+        chunk_ids,
+        content_hash: content_hash(&resource_input.content),
+        media_type: resource_input.media_type,
+    };
+
+    Ok(ResourceInput {
+        media_type: CHUNK_MANIFEST_MEDIA_TYPE.to_owned(),
+        content: serde_json::to_vec(&manifest)?,
+        ..resource_input
+    })
+}
+
+/// join the `chunks` of the `manifest`, verifying the assembled content against the manifest hash
+pub(crate) fn assemble_chunks(
+    manifest: &ChunkManifest,
+    chunks: Vec<Vec<u8>>,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let content = chunks.concat();
+    let hash = content_hash(&content);
+    if !hash.eq_ignore_ascii_case(&manifest.content_hash) {
+        return Err(format!(
+            "Chunked content hash mismatch: expected {}, got {hash}",
+            manifest.content_hash
+        )
+        .into());
+    }
+
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_and_assemble() {
+        let resource_input = ResourceInput {
+            resource_name: String::from("foo"),
+            resource_type: String::from("bar"),
+            media_type: String::from("text/plain"),
+            content: (0..=255).collect(),
+            ..Default::default()
+        };
+
+        let chunks = chunk_resource_inputs(&resource_input, 100);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].content.len(), 56);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.resource_type == "bar#chunk"));
+
+        let manifest_input = manifest_resource_input(
+            resource_input.clone(),
+            vec![String::from("1"), String::from("2"), String::from("3")],
+        )
+        .unwrap();
+        assert_eq!(manifest_input.media_type, CHUNK_MANIFEST_MEDIA_TYPE);
+        let manifest: ChunkManifest = serde_json::from_slice(&manifest_input.content).unwrap();
+        assert_eq!(manifest.media_type, "text/plain");
+
+        let chunk_contents: Vec<_> = chunks.into_iter().map(|chunk| chunk.content).collect();
+        assert_eq!(
+            assemble_chunks(&manifest, chunk_contents.clone()).unwrap(),
+            resource_input.content
+        );
+
+        // out of order chunks are rejected
+        let mut reordered = chunk_contents;
+        reordered.swap(0, 1);
+        assert!(assemble_chunks(&manifest, reordered).is_err());
+    }
+}
@@ -1,6 +1,103 @@
+use std::time::Duration;
+
+use ethers::types::U256;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ContractNetworkConfig {
     pub contract_address: String,
     pub rpc_url: String,
     pub chain_id: u64,
 }
+
+/// How fees are set on resource writing transactions.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum FeeStrategy {
+    /// Let the provider fill in fees (ethers defaults).
+    #[default]
+    ProviderDefault,
+    /// EIP-1559 fees, estimated from recent blocks and adjusted by the given policies.
+    Eip1559 {
+        /// Upper limit for `maxFeePerGas` (wei). The priority fee is also capped by this.
+        max_fee_per_gas_cap: Option<U256>,
+        priority_fee: PriorityFeePolicy,
+    },
+}
+
+/// How the `maxPriorityFeePerGas` (tip) of an EIP-1559 transaction is chosen.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum PriorityFeePolicy {
+    /// Use the estimated priority fee.
+    #[default]
+    Estimated,
+    /// Always use the given priority fee (wei).
+    Fixed(U256),
+    /// Scale the estimated priority fee by a percentage, e.g. `150` tips 50% more than estimated.
+    Percentage(u64),
+}
+
+/// Which `resource_version_id`s the registrar permits for new versions of a resource.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum VersionPolicy {
+    /// Any version id.
+    #[default]
+    Any,
+    /// Version ids must be unique within the resource's versions (empty version ids are exempt).
+    Unique,
+    /// Version ids must be semver, and greater than those of all previous versions.
+    Semver,
+}
+
+/// Several RPC endpoints to read from (see [crate::multi_rpc::MultiRpcClient]), rather than
+/// trusting (and depending on) the single `rpc_url` of a [ContractNetworkConfig].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiRpcConfig {
+    pub rpc_urls: Vec<String>,
+    pub read_policy: ReadPolicy,
+    pub retry_policy: RetryPolicy,
+}
+
+impl MultiRpcConfig {
+    pub fn new(rpc_urls: Vec<String>, read_policy: ReadPolicy) -> Self {
+        Self {
+            rpc_urls,
+            read_policy,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// How reads are spread over the endpoints of a [MultiRpcConfig].
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum ReadPolicy {
+    /// Read from the first endpoint, moving on to the next (in order) if it fails.
+    #[default]
+    Failover,
+    /// Read from all endpoints, and only accept a result (including a JSON-RPC error, such as a
+    /// revert) returned by at least `min_agreeing` of them.
+    Quorum { min_agreeing: usize },
+}
+
+/// How requests to a single endpoint are retried. Only transient failures (connection errors,
+/// timeouts, bad responses & rate limiting) are retried.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for each subsequent retry.
+    pub initial_backoff: Duration,
+    /// Upper limit for the backoff between retries.
+    pub max_backoff: Duration,
+    /// Timeout of each attempt.
+    pub request_timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    error::Error,
+    fmt,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::types::{input::ResourceInput, output::Resource};
+
+/// media type parameter which records the encoding of content on the ledger,
+/// e.g. `application/json;content-encoding=zstd`
+pub(crate) const CONTENT_ENCODING_PARAM: &str = "content-encoding";
+
+const ZSTD_LEVEL: i32 = 19;
+
+/// Maximum length (in bytes) of decoded content. Resources are written by third parties, so a
+/// small encoded resource must not be able to exhaust memory when resolved.
+pub const MAX_DECODED_LEN: u64 = 64 * 1024 * 1024;
+
+/// Compression applied to resource content on the ledger, to reduce the cost of writing large
+/// resources.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    Zstd,
+    Deflate,
+}
+
+impl ContentEncoding {
+    pub fn encode(&self, content: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            ContentEncoding::Zstd => Ok(zstd::encode_all(content, ZSTD_LEVEL)?),
+            ContentEncoding::Deflate => {
+                let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+                encoder.write_all(content)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Decode `content`, failing if it decodes to more than [MAX_DECODED_LEN] bytes.
+    pub fn decode(&self, content: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+        let decoder: Box<dyn Read + '_> = match self {
+            ContentEncoding::Zstd => Box::new(zstd::stream::read::Decoder::new(content)?),
+            ContentEncoding::Deflate => Box::new(DeflateDecoder::new(content)),
+        };
+        let mut decoded = vec![];
+        decoder
+            .take(MAX_DECODED_LEN + 1)
+            .read_to_end(&mut decoded)?;
+        if decoded.len() as u64 > MAX_DECODED_LEN {
+            return Err(
+                format!("{self} content decodes to more than {MAX_DECODED_LEN} bytes").into(),
+            );
+        }
+        Ok(decoded)
+    }
+}
+
+impl fmt::Display for ContentEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentEncoding::Zstd => write!(f, "zstd"),
+            ContentEncoding::Deflate => write!(f, "deflate"),
+        }
+    }
+}
+
+impl FromStr for ContentEncoding {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zstd" => Ok(ContentEncoding::Zstd),
+            "deflate" => Ok(ContentEncoding::Deflate),
+            _ => Err(format!("Unsupported content encoding: {s}").into()),
+        }
+    }
+}
+
+/// Encode the content of the `resource_input` with its `content_encoding` (if any), returning the
+/// input to write to the ledger, which records the encoding in its media type.
+pub(crate) fn encode_resource_input(
+    resource_input: ResourceInput,
+) -> Result<ResourceInput, Box<dyn Error>> {
+    let Some(encoding) = resource_input.content_encoding else {
+        return Ok(resource_input);
+    };
+
+    Ok(ResourceInput {
+        media_type: format!(
+            "{};{CONTENT_ENCODING_PARAM}={encoding}",
+            resource_input.media_type
+        ),
+        content: encoding.encode(&resource_input.content)?,
+        content_encoding: None,
+        ..resource_input
+    })
+}
+
+/// Decode the content of a `resource` read from the ledger, if its media type records a content
+/// encoding. The encoding is moved into the resource metadata. Resources with an unsupported
+/// encoding are returned as-is (still encoded, with the encoding left in their media type).
+pub(crate) fn decode_resource(mut resource: Resource) -> Result<Resource, Box<dyn Error>> {
+    let Ok((media_type, Some(encoding))) = split_media_type_encoding(&resource.metadata.media_type)
+    else {
+        return Ok(resource);
+    };
+
+    resource.content = encoding.decode(&resource.content)?;
+    resource.metadata.media_type = media_type;
+    resource.metadata.content_encoding = Some(encoding);
+    Ok(resource)
+}
+
+/// split the content encoding parameter (if any) from the `media_type`
+fn split_media_type_encoding(
+    media_type: &str,
+) -> Result<(String, Option<ContentEncoding>), Box<dyn Error>> {
+    let mut encoding = None;
+    let mut parts = vec![];
+    for part in media_type.split(';') {
+        match part.trim().split_once('=') {
+            Some((param, value)) if param.trim() == CONTENT_ENCODING_PARAM => {
+                encoding = Some(value.trim().parse()?);
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    Ok((parts.join(";"), encoding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::output::ResourceMetadata;
+
+    #[test]
+    fn test_encode_and_decode() {
+        let content = "hello world ".repeat(100).into_bytes();
+        for encoding in [ContentEncoding::Zstd, ContentEncoding::Deflate] {
+            let encoded = encoding.encode(&content).unwrap();
+            assert!(encoded.len() < content.len());
+            assert_eq!(encoding.decode(&encoded).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_split_media_type_encoding() {
+        assert_eq!(
+            split_media_type_encoding("application/json;content-encoding=zstd").unwrap(),
+            (
+                String::from("application/json"),
+                Some(ContentEncoding::Zstd)
+            )
+        );
+        assert_eq!(
+            split_media_type_encoding("text/plain; charset=utf-8; content-encoding=deflate")
+                .unwrap(),
+            (
+                String::from("text/plain; charset=utf-8"),
+                Some(ContentEncoding::Deflate)
+            )
+        );
+        assert_eq!(
+            split_media_type_encoding("text/plain").unwrap(),
+            (String::from("text/plain"), None)
+        );
+        assert!(split_media_type_encoding("text/plain;content-encoding=br").is_err());
+    }
+
+    #[test]
+    fn test_decode_bomb() {
+        let bomb = |encoder: &mut dyn Write| {
+            std::io::copy(&mut std::io::repeat(0).take(MAX_DECODED_LEN + 1), encoder).unwrap();
+        };
+
+        let mut encoder = zstd::stream::write::Encoder::new(vec![], 1).unwrap();
+        bomb(&mut encoder);
+        let zstd_bomb = encoder.finish().unwrap();
+
+        let mut encoder = DeflateEncoder::new(vec![], Compression::fast());
+        bomb(&mut encoder);
+        let deflate_bomb = encoder.finish().unwrap();
+
+        for (encoding, encoded) in [
+            (ContentEncoding::Zstd, zstd_bomb),
+            (ContentEncoding::Deflate, deflate_bomb),
+        ] {
+            assert!(encoded.len() < 1024 * 1024);
+            assert!(encoding.decode(&encoded).is_err());
+        }
+    }
+
+    #[test]
+    fn test_decode_resource_unsupported_encoding() {
+        let resource = Resource {
+            metadata: ResourceMetadata {
+                resource_uri: String::from("did:ethr:0x01/resources/1"),
+                resource_type: String::from("type"),
+                resource_name: String::from("name"),
+                resource_id: Some(String::from("1")),
+                resource_collection_id: None,
+                resource_version_id: None,
+                media_type: String::from("text/plain;content-encoding=br"),
+                created: Default::default(),
+                checksum: None,
+                previous_version_id: None,
+                next_version_id: None,
+                content_encoding: None,
+                deactivated: false,
+                controller_at_creation: None,
+            },
+            content: vec![1, 2, 3],
+        };
+        assert_eq!(decode_resource(resource.clone()).unwrap(), resource);
+    }
+}
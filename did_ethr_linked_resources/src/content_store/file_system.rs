@@ -0,0 +1,123 @@
+use std::{
+    error::Error,
+    path::{Component, PathBuf},
+};
+
+use async_trait::async_trait;
+use tokio::fs;
+use url::Url;
+
+use super::{content_hash, ContentStore};
+
+const URI_SCHEME: &str = "file:";
+
+/// [ContentStore] which keeps content in a local directory, named by the content hash. Content
+/// URIs are `file:` followed by the content hash, relative to the store directory, so that they
+/// don't expose the writer's filesystem, and content is only ever read from within the directory.
+pub struct FileSystemContentStore {
+    directory: PathBuf,
+}
+
+impl FileSystemContentStore {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    /// the path of the content at `uri`, which must be within the store directory
+    async fn content_path(&self, uri: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let directory = fs::canonicalize(&self.directory).await?;
+
+        // absolute `file://` URLs, as written by earlier versions
+        if uri.starts_with("file://") {
+            let path = Url::parse(uri)?
+                .to_file_path()
+                .map_err(|_| format!("Invalid file URI: {uri}"))?;
+            let is_within_directory = path.starts_with(&directory)
+                && path
+                    .components()
+                    .all(|component| !matches!(component, Component::ParentDir));
+            if !is_within_directory {
+                return Err(format!("{uri} is outside of the content store directory").into());
+            }
+            return Ok(path);
+        }
+
+        let name = uri
+            .strip_prefix(URI_SCHEME)
+            .ok_or_else(|| format!("Invalid file URI: {uri}"))?;
+        if !is_content_hash(name) {
+            return Err(format!("{uri} is not the URI of content in the store").into());
+        }
+        Ok(directory.join(name))
+    }
+}
+
+#[async_trait]
+impl ContentStore for FileSystemContentStore {
+    async fn put(&self, content: &[u8]) -> Result<String, Box<dyn Error>> {
+        fs::create_dir_all(&self.directory).await?;
+        let name = content_hash(content);
+        fs::write(self.directory.join(&name), content).await?;
+
+        Ok(format!("{URI_SCHEME}{name}"))
+    }
+
+    async fn get(&self, uri: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let path = self.content_path(uri).await?;
+        Ok(fs::read(path).await?)
+    }
+
+    fn supports(&self, uri: &str) -> bool {
+        uri.starts_with(URI_SCHEME)
+    }
+}
+
+/// whether the `name` is a hex encoded keccak256 hash (see [content_hash])
+fn is_content_hash(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_put_and_get() {
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let store = FileSystemContentStore::new(&directory);
+
+        let uri = store.put(b"hello world").await.unwrap();
+        assert!(store.supports(&uri));
+        assert_eq!(store.get(&uri).await.unwrap(), b"hello world");
+        assert_eq!(uri, format!("file:{}", content_hash(b"hello world")));
+
+        // content addressed
+        assert_eq!(store.put(b"hello world").await.unwrap(), uri);
+
+        // absolute URLs within the directory
+        let path = fs::canonicalize(&directory)
+            .await
+            .unwrap()
+            .join(content_hash(b"hello world"));
+        let absolute_uri = Url::from_file_path(&path).unwrap().to_string();
+        assert_eq!(store.get(&absolute_uri).await.unwrap(), b"hello world");
+
+        // nothing outside of the directory
+        let outside = fs::canonicalize(std::env::temp_dir()).await.unwrap();
+        for uri in [
+            String::from("file:../hello"),
+            format!("file:{}/..", content_hash(b"hello world")),
+            Url::from_file_path(outside.join("hello"))
+                .unwrap()
+                .to_string(),
+            Url::from_file_path(&path).unwrap().to_string() + "/../..",
+            String::from("file:///etc/hostname"),
+        ] {
+            assert!(store.get(&uri).await.is_err(), "{uri}");
+        }
+
+        fs::remove_dir_all(directory).await.unwrap();
+    }
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+
+use super::ContentStore;
+
+const IPFS_URI_SCHEME: &str = "ipfs://";
+
+/// [ContentStore] backed by an IPFS node, via its HTTP RPC API (e.g. `http://127.0.0.1:5001`).
+/// Content URIs are `ipfs://<cid>`.
+pub struct IpfsHttpContentStore {
+    api_url: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct AddResponse {
+    hash: String,
+}
+
+impl IpfsHttpContentStore {
+    pub fn new(api_url: &str) -> Self {
+        Self {
+            api_url: api_url.trim_end_matches('/').to_owned(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl ContentStore for IpfsHttpContentStore {
+    async fn put(&self, content: &[u8]) -> Result<String, Box<dyn Error>> {
+        let form = Form::new().part("file", Part::bytes(content.to_vec()));
+        let res = self
+            .client
+            .post(format!("{}/api/v0/add?pin=true", self.api_url))
+            .multipart(form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<AddResponse>()
+            .await?;
+
+        Ok(format!("{IPFS_URI_SCHEME}{}", res.hash))
+    }
+
+    async fn get(&self, uri: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let cid = uri
+            .strip_prefix(IPFS_URI_SCHEME)
+            .ok_or_else(|| format!("Invalid IPFS URI: {uri}"))?;
+        let content = self
+            .client
+            .post(format!("{}/api/v0/cat", self.api_url))
+            .query(&[("arg", cid)])
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(content.to_vec())
+    }
+
+    fn supports(&self, uri: &str) -> bool {
+        uri.starts_with(IPFS_URI_SCHEME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use super::*;
+    use crate::content_store::content_hash;
+
+    /// Minimal stand-in for the IPFS HTTP API (`add` & `cat` only), returning the address it
+    /// listens on.
+    fn spawn_ipfs_stand_in() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        thread::spawn(move || {
+            let mut blocks = HashMap::new();
+            for stream in listener.incoming() {
+                handle_request(stream.unwrap(), &mut blocks);
+            }
+        });
+
+        address
+    }
+
+    fn handle_request(mut stream: TcpStream, blocks: &mut HashMap<String, Vec<u8>>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let path = request_line.split(' ').nth(1).unwrap().to_owned();
+
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let Some((name, value)) = line.trim_end().split_once(": ") else {
+                break;
+            };
+            headers.insert(name.to_lowercase(), value.to_owned());
+        }
+        let content_length = headers
+            .get("content-length")
+            .map_or(0, |len| len.parse().unwrap());
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).unwrap();
+
+        let (status, response) = if path.starts_with("/api/v0/add") {
+            let boundary = headers["content-type"].split("boundary=").nth(1).unwrap();
+            let content = multipart_file_content(&body, boundary);
+            let cid = format!("bafy{}", content_hash(&content));
+            let response = format!(
+                r#"{{"Name":"file","Hash":"{cid}","Size":"{}"}}"#,
+                content.len()
+            );
+            blocks.insert(cid, content);
+            ("200 OK", response.into_bytes())
+        } else if let Some(cid) = path.strip_prefix("/api/v0/cat?arg=") {
+            match blocks.get(cid) {
+                Some(content) => ("200 OK", content.clone()),
+                None => ("500 Internal Server Error", b"not found".to_vec()),
+            }
+        } else {
+            ("404 Not Found", vec![])
+        };
+
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.len()
+        )
+        .unwrap();
+        stream.write_all(&response).unwrap();
+    }
+
+    fn multipart_file_content(body: &[u8], boundary: &str) -> Vec<u8> {
+        let find = |haystack: &[u8], needle: &[u8]| {
+            haystack
+                .windows(needle.len())
+                .position(|window| window == needle)
+                .unwrap()
+        };
+        let start = find(body, b"\r\n\r\n") + 4;
+        let end = start + find(&body[start..], format!("\r\n--{boundary}").as_bytes());
+        body[start..end].to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_put_and_get() {
+        let store = IpfsHttpContentStore::new(&spawn_ipfs_stand_in());
+
+        let uri = store.put(b"hello world").await.unwrap();
+        assert!(uri.starts_with("ipfs://"));
+        assert!(store.supports(&uri));
+        assert_eq!(store.get(&uri).await.unwrap(), b"hello world");
+
+        assert!(store.get("ipfs://bafyunknown").await.is_err());
+        assert!(store.get("file:///tmp/foo").await.is_err());
+    }
+}
@@ -0,0 +1,97 @@
+use std::error::Error;
+
+use async_trait::async_trait;
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{input::ResourceInput, output::Resource};
+
+pub mod file_system;
+pub mod ipfs;
+
+/// Media type of resources whose on-chain content is a [ContentReference], rather than the
+/// content itself.
+pub const CONTENT_REFERENCE_MEDIA_TYPE: &str = "application/vnd.ethr-dlr.content-reference+json";
+
+/// Off-chain storage for resource content. Only a [ContentReference] (URI & hash) to the content
+/// is then written to the ledger, saving on the cost of large resources.
+#[async_trait]
+pub trait ContentStore: Send + Sync {
+    /// Store the `content`, returning a URI which it can be fetched from via [Self::get].
+    async fn put(&self, content: &[u8]) -> Result<String, Box<dyn Error>>;
+
+    /// Fetch the content at `uri`.
+    async fn get(&self, uri: &str) -> Result<Vec<u8>, Box<dyn Error>>;
+
+    /// Whether this store can fetch the content at `uri` (e.g. by the URI scheme).
+    fn supports(&self, uri: &str) -> bool;
+}
+
+/// The on-chain content of a resource which is stored in a [ContentStore].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentReference {
+    /// where the content is stored
+    pub uri: String,
+    /// hex encoded keccak256 hash of the content, verified when the content is fetched
+    pub content_hash: String,
+    /// media type of the content
+    pub media_type: String,
+}
+
+/// hex encoded keccak256 hash of resource `content`
+pub fn content_hash(content: &[u8]) -> String {
+    hex::encode(keccak256(content))
+}
+
+/// Store the content of the `resource_input` in the `store`, returning the resource input which
+/// should be written to the ledger instead (containing a [ContentReference]).
+pub async fn offload_content(
+    store: &dyn ContentStore,
+    resource_input: ResourceInput,
+) -> Result<ResourceInput, Box<dyn Error>> {
+    let uri = store.put(&resource_input.content).await?;
+    let reference = ContentReference {
+        uri,
+        content_hash: content_hash(&resource_input.content),
+        media_type: resource_input.media_type,
+    };
+
+    Ok(ResourceInput {
+        media_type: CONTENT_REFERENCE_MEDIA_TYPE.to_owned(),
+        content: serde_json::to_vec(&reference)?,
+        ..resource_input
+    })
+}
+
+/// If the `resource` content is a [ContentReference], fetch the content from a supporting store
+/// (of `stores`) and verify it against the reference's hash. Otherwise, the `resource` is returned
+/// as is.
+pub async fn dereference_content(
+    stores: &[std::sync::Arc<dyn ContentStore>],
+    mut resource: Resource,
+) -> Result<Resource, Box<dyn Error>> {
+    if resource.metadata.media_type != CONTENT_REFERENCE_MEDIA_TYPE {
+        return Ok(resource);
+    }
+
+    let reference: ContentReference = serde_json::from_slice(&resource.content)?;
+    let Some(store) = stores.iter().find(|store| store.supports(&reference.uri)) else {
+        return Err(format!("No content store configured for {}", reference.uri).into());
+    };
+
+    let content = store.get(&reference.uri).await?;
+    let hash = content_hash(&content);
+    if !hash.eq_ignore_ascii_case(&reference.content_hash) {
+        return Err(format!(
+            "Content hash mismatch for {}: expected {}, got {hash}",
+            reference.uri, reference.content_hash
+        )
+        .into());
+    }
+
+    resource.content = content;
+    resource.metadata.media_type = reference.media_type;
+    resource.metadata.checksum = Some(hash);
+    Ok(resource)
+}
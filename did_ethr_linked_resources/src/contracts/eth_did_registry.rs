@@ -1,8 +1,15 @@
 use std::sync::Arc;
 
-use ethers::{abi::Address, providers::Middleware, types::H160};
+use ethers::{
+    abi::Address,
+    providers::Middleware,
+    types::{H160, U256},
+};
 
-use crate::{config::ContractNetworkConfig, utils::full_did_into_did_identity};
+use crate::{
+    config::ContractNetworkConfig,
+    utils::{delegate_type_as_bytes32, full_did_into_did_identity},
+};
 
 // Include generated contract types from build script
 include!(concat!(
@@ -40,4 +47,54 @@ impl DidEthRegistry {
             .await
             .unwrap();
     }
+
+    /// Add `delegate` as a delegate of the DID, of the type `delegate_type` (e.g. "veriKey"),
+    /// valid for `validity_secs` seconds.
+    pub async fn add_delegate(
+        &self,
+        signer: Arc<impl Middleware>,
+        did: &str,
+        delegate_type: &str,
+        delegate: H160,
+        validity_secs: u64,
+    ) {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type).unwrap();
+
+        contract
+            .add_delegate(
+                did_identity,
+                delegate_type,
+                delegate,
+                U256::from(validity_secs),
+            )
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+    }
+
+    pub async fn revoke_delegate(
+        &self,
+        signer: Arc<impl Middleware>,
+        did: &str,
+        delegate_type: &str,
+        delegate: H160,
+    ) {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type).unwrap();
+
+        contract
+            .revoke_delegate(did_identity, delegate_type, delegate)
+            .send()
+            .await
+            .unwrap()
+            .await
+            .unwrap();
+    }
 }
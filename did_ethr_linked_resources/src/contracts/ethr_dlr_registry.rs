@@ -1,77 +1,454 @@
 use std::error::Error;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use anyhow::anyhow;
 use ethers::abi::RawLog;
-use ethers::contract::EthEvent;
-use ethers::providers::{Http, Provider};
-use ethers::types::H160;
+use ethers::contract::{ContractCall, ContractError, EthEvent, LogMeta};
+use ethers::types::{TransactionReceipt, H160, H256};
+use ethers::utils::keccak256;
 use ethers::{abi::Address, providers::Middleware, types::U256};
 
 use crate::config::ContractNetworkConfig;
-use crate::types::input::ResourceInput;
-use crate::utils::full_did_into_did_identity;
+use crate::contracts::eth_did_registry::EthereumDIDRegistry;
+use crate::contracts::registry_version::{
+    detect_registry_version, RegistryVersion, VersionedRegistry,
+};
+use crate::error::RegistryRevert;
+use crate::telemetry::{self, HttpProvider};
+use crate::types::input::{ResourceInput, SignedResourceInput};
+use crate::types::output::ControllerAtCreation;
+use crate::utils::{delegate_type_as_bytes32, full_did_into_did_identity};
 
 // Include generated contract types from build script
 include!(concat!(env!("OUT_DIR"), "/ethr_dlr_registry_contract.rs"));
 
-pub struct EthrDIDLinkedResourcesRegistry {
+/// Reads from (and writes to) the registry. Reads are made through a single `client`, so any
+/// middleware stack (retries, rate limiting, etc.) and its connections are shared across reads.
+pub struct EthrDIDLinkedResourcesRegistry<M = HttpProvider> {
     contract_address: Address,
-    rpc_url: String,
+    client: Arc<M>,
+    version: OnceLock<RegistryVersion>,
 }
 
 impl EthrDIDLinkedResourcesRegistry {
+    /// Read from the registry with a HTTP provider for the `config.rpc_url`.
     pub fn new(config: ContractNetworkConfig) -> Self {
+        let provider = telemetry::http_provider(&config.rpc_url).unwrap();
+        Self::with_client(config, Arc::new(provider))
+    }
+}
+
+impl<M: Middleware + 'static> EthrDIDLinkedResourcesRegistry<M> {
+    /// Read from the registry with the given `client` (the `config.rpc_url` is not used).
+    pub fn with_client(config: ContractNetworkConfig, client: Arc<M>) -> Self {
         Self {
             contract_address: config.contract_address.parse().unwrap(),
-            rpc_url: config.rpc_url,
+            client,
+            version: OnceLock::new(),
+        }
+    }
+
+    /// The version of the deployed registry (detected once, then cached).
+    pub async fn registry_version(&self) -> Result<RegistryVersion, Box<dyn Error>> {
+        let cached_version = self.version.get();
+        telemetry::cache_lookup("registry_version", cached_version.is_some());
+        if let Some(version) = cached_version {
+            return Ok(*version);
         }
+
+        let client = self.get_read_only_ethers_client();
+        let version = detect_registry_version(self.contract_address, client).await?;
+
+        Ok(*self.version.get_or_init(|| version))
+    }
+
+    /// the bindings matching the version of the deployed registry, for the functions whose ABI
+    /// differs between versions
+    async fn versioned_contract(&self) -> Result<VersionedRegistry<M>, Box<dyn Error>> {
+        let version = self.registry_version().await?;
+        let client = self.get_read_only_ethers_client();
+        Ok(VersionedRegistry::new(
+            version,
+            self.contract_address,
+            client,
+        ))
     }
 
     fn contract_with_client<T: Middleware>(&self, client: Arc<T>) -> EthrDLRRegistry<T> {
         EthrDLRRegistry::new(self.contract_address.clone(), client)
     }
 
-    fn get_read_only_ethers_client(&self) -> Arc<impl Middleware> {
-        let provider = Provider::<Http>::try_from(&self.rpc_url).unwrap();
-        Arc::new(provider)
+    fn get_read_only_ethers_client(&self) -> Arc<M> {
+        self.client.clone()
+    }
+
+    pub async fn create_or_update_resource<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        resource: ResourceInput,
+    ) -> Result<NewResourceFilter, Box<dyn Error>> {
+        let tx = self.create_resource_call(signer, did, resource);
+
+        Self::send_new_resource_tx(tx).await
     }
 
-    pub async fn create_or_update_resource(
+    /// Create a resource for the `did`, where the `signer` is authorized as a delegate of
+    /// the DID (of type `delegate_type`, e.g. "veriKey") rather than the DID controller.
+    pub async fn create_or_update_resource_as_delegate<S: Middleware>(
         &self,
-        signer: Arc<impl Middleware>,
+        signer: Arc<S>,
         did: &str,
+        delegate_type: &str,
         resource: ResourceInput,
     ) -> Result<NewResourceFilter, Box<dyn Error>> {
+        let tx = self.create_resource_as_delegate_call(signer, did, delegate_type, resource)?;
+
+        Self::send_new_resource_tx(tx).await
+    }
+
+    /// Create a resource on behalf of the DID controller, where the controller has signed the
+    /// resource offline (see [SignedResourceInput::sign]). The `relayer` submits (and pays for)
+    /// the transaction.
+    pub async fn create_or_update_resource_signed<S: Middleware>(
+        &self,
+        relayer: Arc<S>,
+        signed_resource: SignedResourceInput,
+    ) -> Result<NewResourceFilter, Box<dyn Error>> {
+        let tx = self.create_resource_signed_call(relayer, signed_resource);
+
+        Self::send_new_resource_tx(tx).await
+    }
+
+    /// Build (but do not send) the contract call for [Self::create_or_update_resource].
+    /// Useful for estimating, simulating or customizing the transaction before sending.
+    pub fn create_resource_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        resource: ResourceInput,
+    ) -> ContractCall<S, ()> {
         let contract = self.contract_with_client(signer);
 
         let did_identity = full_did_into_did_identity(did);
 
-        let tx = contract
-            .create_resource(
-                did_identity,
-                resource.resource_name,
-                resource.resource_type,
-                resource.resource_version_id,
-                resource.media_type,
-                resource.content.into(),
-            )
-            .send()
+        contract.create_resource(
+            did_identity,
+            resource.resource_name,
+            resource.resource_type,
+            resource.resource_version_id,
+            resource.media_type,
+            resource.content.into(),
+        )
+    }
+
+    /// Build (but do not send) the contract call for [Self::create_or_update_resource_as_delegate].
+    pub fn create_resource_as_delegate_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        delegate_type: &str,
+        resource: ResourceInput,
+    ) -> Result<ContractCall<S, ()>, Box<dyn Error>> {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type)?;
+
+        Ok(contract.create_resource_as_delegate(
+            did_identity,
+            delegate_type,
+            resource.resource_name,
+            resource.resource_type,
+            resource.resource_version_id,
+            resource.media_type,
+            resource.content.into(),
+        ))
+    }
+
+    /// Build (but do not send) the contract call for writing all `resources` in a single
+    /// transaction.
+    pub fn create_resources_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        resources: Vec<ResourceInput>,
+    ) -> ContractCall<S, ()> {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+
+        contract.create_resources(
+            did_identity,
+            resources.into_iter().map(resource_input_data).collect(),
+        )
+    }
+
+    /// Build (but do not send) the contract call for writing all `resources` in a single
+    /// transaction, as a delegate of the DID.
+    pub fn create_resources_as_delegate_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        delegate_type: &str,
+        resources: Vec<ResourceInput>,
+    ) -> Result<ContractCall<S, ()>, Box<dyn Error>> {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type)?;
+
+        Ok(contract.create_resources_as_delegate(
+            did_identity,
+            delegate_type,
+            resources.into_iter().map(resource_input_data).collect(),
+        ))
+    }
+
+    /// Build (but do not send) the contract call for deactivating the resource (by appending a
+    /// tombstone version).
+    pub fn deactivate_resource_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> ContractCall<S, ()> {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+
+        contract.deactivate_resource(
+            did_identity,
+            resource_name.to_owned(),
+            resource_type.to_owned(),
+        )
+    }
+
+    /// Build (but do not send) the contract call for deactivating the resource, as a delegate of
+    /// the DID.
+    pub fn deactivate_resource_as_delegate_call<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+        did: &str,
+        delegate_type: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<ContractCall<S, ()>, Box<dyn Error>> {
+        let contract = self.contract_with_client(signer);
+
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type)?;
+
+        Ok(contract.deactivate_resource_as_delegate(
+            did_identity,
+            delegate_type,
+            resource_name.to_owned(),
+            resource_type.to_owned(),
+        ))
+    }
+
+    /// Whether the latest version of the resource is a tombstone (see [TOMBSTONE_MEDIA_TYPE]).
+    pub async fn is_resource_deactivated(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        let did_identity = full_did_into_did_identity(did);
+
+        self.versioned_contract()
+            .await?
+            .is_resource_deactivated(did_identity, resource_name, resource_type)
             .await
-            .map_err(|e| anyhow!(e.to_string()))?
+    }
+
+    /// Build (but do not send) the contract call for [Self::create_or_update_resource_signed].
+    pub fn create_resource_signed_call<S: Middleware>(
+        &self,
+        relayer: Arc<S>,
+        signed_resource: SignedResourceInput,
+    ) -> ContractCall<S, ()> {
+        let contract = self.contract_with_client(relayer);
+
+        let did_identity = full_did_into_did_identity(&signed_resource.did);
+        let signature = signed_resource.signature;
+        let resource = signed_resource.resource;
+
+        contract.create_resource_signed(
+            did_identity,
+            signature.v as u8,
+            u256_as_bytes32(signature.r),
+            u256_as_bytes32(signature.s),
+            resource.resource_name,
+            resource.resource_type,
+            resource.resource_version_id,
+            resource.media_type,
+            resource.content.into(),
+        )
+    }
+
+    /// Recover the address which signed the [SignedResourceInput], for this registry.
+    pub fn recover_signed_resource_signer(
+        &self,
+        signed_resource: &SignedResourceInput,
+    ) -> Result<H160, Box<dyn Error>> {
+        let hash = create_resource_signed_hash(
+            self.contract_address,
+            signed_resource.nonce,
+            full_did_into_did_identity(&signed_resource.did),
+            &signed_resource.resource,
+        );
+
+        Ok(signed_resource.signature.recover(hash)?)
+    }
+
+    /// Get the current nonce for signed writes (`createResourceSigned`) of the `controller`
+    /// (the DID identity owner).
+    pub async fn get_signed_write_nonce(&self, controller: H160) -> Result<U256, Box<dyn Error>> {
+        let client = self.get_read_only_ethers_client();
+        let contract = self.contract_with_client(client);
+
+        Ok(contract.nonce(controller).call().await?)
+    }
+
+    /// Get the current controller (identity owner) of the DID, according to the
+    /// `EthereumDIDRegistry` which this registry authorizes writes against.
+    pub async fn get_did_controller(&self, did: &str) -> Result<H160, Box<dyn Error>> {
+        let did_registry = self.did_registry_contract().await?;
+        let did_identity = full_did_into_did_identity(did);
+
+        Ok(did_registry.identity_owner(did_identity).call().await?)
+    }
+
+    /// Whether `delegate` is currently a valid delegate of the DID, of type `delegate_type`.
+    pub async fn is_valid_did_delegate(
+        &self,
+        did: &str,
+        delegate_type: &str,
+        delegate: H160,
+    ) -> Result<bool, Box<dyn Error>> {
+        let did_registry = self.did_registry_contract().await?;
+        let did_identity = full_did_into_did_identity(did);
+        let delegate_type = delegate_type_as_bytes32(delegate_type)?;
+
+        Ok(did_registry
+            .valid_delegate(did_identity, delegate_type, delegate)
+            .call()
+            .await?)
+    }
+
+    /// Audit who controlled the DID when the resource `resource_id` was written: the owner of
+    /// the DID at that point (by replaying the `DIDOwnerChanged` events of the
+    /// `EthereumDIDRegistry`), and the sender of the transaction which wrote it.
+    pub async fn get_controller_at_creation(
+        &self,
+        did: &str,
+        resource_id: &str,
+    ) -> Result<ControllerAtCreation, Box<dyn Error>> {
+        let log_meta = self
+            .get_resource_log_meta(did, resource_id)
+            .await?
+            .ok_or_else(|| format!("Resource {resource_id} of {did} not found"))?;
+
+        let controller = self.get_did_controller_at(did, &log_meta).await?;
+
+        let client = self.get_read_only_ethers_client();
+        let receipt = client
+            .get_transaction_receipt(log_meta.transaction_hash)
+            .await?
+            .ok_or_else(|| format!("Receipt of {:?} not found", log_meta.transaction_hash))?;
+
+        Ok(ControllerAtCreation {
+            controller,
+            tx_sender: receipt.from,
+            block_number: log_meta.block_number.as_u64(),
+        })
+    }
+
+    /// The owner of the DID as of the log at `log_meta` (exclusive), replaying its
+    /// `DIDOwnerChanged` events. DIDs which were never changed are owned by their identity.
+    async fn get_did_controller_at(
+        &self,
+        did: &str,
+        log_meta: &LogMeta,
+    ) -> Result<H160, Box<dyn Error>> {
+        let did_registry = self.did_registry_contract().await?;
+        let did_identity = full_did_into_did_identity(did);
+
+        let mut filter = did_registry.did_owner_changed_filter();
+        filter.filter = filter
+            .filter
+            .topic1(did_identity)
+            .from_block(0)
+            .to_block(log_meta.block_number);
+        let mut owner_changes = filter
+            .query_with_meta()
             .await
-            .map_err(|e| anyhow!(e.to_string()))?
-            .unwrap();
+            .map_err(decode_contract_error)?;
+        owner_changes.sort_by_key(|(_, meta)| (meta.block_number, meta.log_index));
 
-        let resource_update_event = tx
-            .logs
+        // the last change before the log
+        let owner = owner_changes
             .into_iter()
-            .find_map(|log| {
-                let event = NewResourceFilter::decode_log(&RawLog::from(log));
-                event.ok()
+            .rev()
+            .find(|(_, meta)| {
+                (meta.block_number, meta.log_index) < (log_meta.block_number, log_meta.log_index)
             })
+            .map_or(did_identity, |(change, _)| change.owner);
+        Ok(owner)
+    }
+
+    async fn get_resource_log_meta(
+        &self,
+        did: &str,
+        resource_id: &str,
+    ) -> Result<Option<LogMeta>, Box<dyn Error>> {
+        let client = self.get_read_only_ethers_client();
+        let contract = self.contract_with_client(client);
+
+        let did_identity = full_did_into_did_identity(did);
+        let resource_id = U256::from_dec_str(resource_id)?;
+
+        let mut filter = contract.new_resource_filter();
+        filter.filter = filter
+            .filter
+            .address(self.contract_address)
+            .topic1(did_identity)
+            .topic2(resource_id)
+            .from_block(0);
+
+        let events = filter
+            .query_with_meta()
+            .await
+            .map_err(decode_contract_error)?;
+        Ok(events.into_iter().next().map(|(_, meta)| meta))
+    }
+
+    async fn did_registry_contract(&self) -> Result<EthereumDIDRegistry<M>, Box<dyn Error>> {
+        let client = self.get_read_only_ethers_client();
+        let contract = self.contract_with_client(client.clone());
+
+        let did_registry_address = contract.did_registry().call().await?;
+
+        Ok(EthereumDIDRegistry::new(did_registry_address, client))
+    }
+
+    /// Send a resource creating contract call (see [Self::create_resource_call]), and wait for
+    /// the resulting [NewResourceFilter] event.
+    pub async fn send_new_resource_tx<S: Middleware>(
+        tx: ContractCall<S, ()>,
+    ) -> Result<NewResourceFilter, Box<dyn Error>> {
+        let tx = tx
+            .send()
+            .await
+            .map_err(decode_contract_error)?
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?
             .unwrap();
 
+        let resource_update_event = decode_new_resource_event(tx).unwrap();
+
         Ok(resource_update_event)
     }
 
@@ -79,9 +456,10 @@ impl EthrDIDLinkedResourcesRegistry {
         &self,
         did: &str,
         resource_id: &str,
-    ) -> Option<NewResourceFilter> {
+    ) -> Result<Option<NewResourceFilter>, Box<dyn Error>> {
         let did_identity = full_did_into_did_identity(did);
-        let resource_id = U256::from_dec_str(resource_id).unwrap();
+        let resource_id = U256::from_dec_str(resource_id)
+            .map_err(|e| format!("Invalid resource id {resource_id:?}: {e}"))?;
 
         self.get_resource_by_id_raw(did_identity, resource_id).await
     }
@@ -90,7 +468,7 @@ impl EthrDIDLinkedResourcesRegistry {
         &self,
         did_identity: H160,
         resource_id: U256,
-    ) -> Option<NewResourceFilter> {
+    ) -> Result<Option<NewResourceFilter>, Box<dyn Error>> {
         let client = self.get_read_only_ethers_client();
         let contract = self.contract_with_client(client);
 
@@ -103,14 +481,17 @@ impl EthrDIDLinkedResourcesRegistry {
             .from_block(0);
 
         // Query this event filter on the contract
-        let events: Vec<NewResourceFilter> = precise_filter.query().await.unwrap();
+        let events: Vec<NewResourceFilter> = precise_filter
+            .query()
+            .await
+            .map_err(decode_contract_error)?;
         let mut events = events.into_iter();
 
-        return match (events.next(), events.next()) {
-            (Some(event), None) => Some(event),
-            (None, None) => None,
-            _ => panic!("Multiple events found for resource id: {}", resource_id),
-        };
+        match (events.next(), events.next()) {
+            (Some(event), None) => Ok(Some(event)),
+            (None, None) => Ok(None),
+            _ => Err(format!("Multiple events found for resource id: {resource_id}").into()),
+        }
     }
 
     pub async fn get_resource_by_name_and_type_at_epoch(
@@ -119,68 +500,177 @@ impl EthrDIDLinkedResourcesRegistry {
         resource_name: &str,
         resource_type: &str,
         epoch: u64,
-    ) -> Option<(NewResourceFilter, ResourceVersionMetadataChainNode)> {
-        let client = self.get_read_only_ethers_client();
-        let contract = self.contract_with_client(client.clone());
-
+    ) -> Result<Option<(NewResourceFilter, ResourceVersionMetadataChainNode)>, Box<dyn Error>> {
         let did_identity = full_did_into_did_identity(did);
 
-        let resource_name_and_type = format!("{}{}", resource_name, resource_type);
+        let metadata_chain: Vec<ResourceVersionMetadataChainNode> = self
+            .versioned_contract()
+            .await?
+            .get_resource_metadata_chain(did_identity, resource_name, resource_type)
+            .await?;
+
+        // the latest version created at or before the epoch. Versions written in the same block
+        // share its timestamp, so this must find the last of them (which a binary search for the
+        // timestamp may not)
+        let idx = metadata_chain.partition_point(|node| node.created.block_timestamp <= epoch);
+        if idx == 0 {
+            // this indicates that the epoch is before the first version
+            return Ok(None);
+        }
+        let metadata_node = metadata_chain.into_iter().nth(idx - 1).unwrap();
 
-        let metadata_chain: Vec<ResourceVersionMetadataChainNode> = contract
-            .get_resource_metadata_chain(did_identity, resource_name_and_type)
-            .call()
-            .await
-            .unwrap();
+        let resource = self
+            .get_resource_by_id_raw(did_identity, metadata_node.resource_id)
+            .await?
+            .ok_or_else(|| format!("Resource {} of {did} not found", metadata_node.resource_id))?;
+        Ok(Some((resource, metadata_node)))
+    }
 
-        let search_res =
-            metadata_chain.binary_search_by(|node| node.created.block_timestamp.cmp(&epoch));
-
-        let metadata_node = match search_res {
-            Ok(idx) => metadata_chain.into_iter().nth(idx).unwrap(),
-            Err(idx) => {
-                if idx == 0 {
-                    // this indicates that the epoch is before the first version
-                    return None;
-                }
-                metadata_chain.into_iter().nth(idx - 1).unwrap()
-            }
+    /// The latest version of the resource (by name & type) on the ledger, if any.
+    pub async fn get_latest_resource_by_name_and_type(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<Option<(NewResourceFilter, ResourceVersionMetadataChainNode)>, Box<dyn Error>> {
+        let did_identity = full_did_into_did_identity(did);
+
+        let Some(metadata_node) = self
+            .versioned_contract()
+            .await?
+            .get_resource_metadata_chain_latest_node(did_identity, resource_name, resource_type)
+            .await?
+        else {
+            return Ok(None);
         };
 
-        Some((
-            self.get_resource_by_id_raw(did_identity, metadata_node.resource_id)
-                .await
-                .unwrap(),
-            metadata_node,
-        ))
+        let resource = self
+            .get_resource_by_id_raw(did_identity, metadata_node.resource_id)
+            .await?
+            .ok_or_else(|| format!("Resource {} of {did} not found", metadata_node.resource_id))?;
+        Ok(Some((resource, metadata_node)))
     }
 
-    pub async fn get_resource_metadata_chain_node(
+    /// All versions of the resource (by name & type), in the order they were written.
+    pub async fn get_resource_versions(
         &self,
         did: &str,
         resource_name: &str,
         resource_type: &str,
-        index: u64,
-    ) -> ResourceVersionMetadataChainNode {
+    ) -> Result<Vec<NewResourceFilter>, Box<dyn Error>> {
         let client = self.get_read_only_ethers_client();
-        let contract = self.contract_with_client(client.clone());
+        let contract = self.contract_with_client(client);
 
         let did_identity = full_did_into_did_identity(did);
+        let resource_topic = self
+            .registry_version()
+            .await?
+            .resource_topic(resource_name, resource_type);
 
-        let resource_name_and_type = format!("{}{}", resource_name, resource_type);
+        let mut filter = contract.new_resource_filter();
+        filter.filter = filter
+            .filter
+            .address(self.contract_address)
+            .topic1(did_identity)
+            .topic3(resource_topic)
+            .from_block(0);
 
-        contract
-            .get_resource_metadata_chain_node(
-                did_identity,
-                resource_name_and_type,
-                U256::from(index),
-            )
-            .call()
+        let mut events: Vec<NewResourceFilter> =
+            filter.query().await.map_err(decode_contract_error)?;
+        events.sort_by_key(|event| event.resource_id);
+        Ok(events)
+    }
+
+    pub async fn get_resource_metadata_chain_node(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+        index: u64,
+    ) -> ResourceVersionMetadataChainNode {
+        let did_identity = full_did_into_did_identity(did);
+
+        self.versioned_contract()
             .await
             .unwrap()
+            .get_resource_metadata_chain_node(did_identity, resource_name, resource_type, index)
+            .await
+            .unwrap()
+    }
+}
+
+/// Media type of the (empty) tombstone version which is appended to a resource when it is
+/// deactivated. Mirrors `TOMBSTONE_MEDIA_TYPE` of the contract.
+pub const TOMBSTONE_MEDIA_TYPE: &str = "application/vnd.ethr-dlr.tombstone";
+
+/// Registry-style hash of a resource, which the DID controller signs to authorize a relayed
+/// `createResourceSigned` write. Mirrors `createResourceSignedHash` of the contract.
+pub fn create_resource_signed_hash(
+    contract_address: Address,
+    nonce: U256,
+    did_identity: H160,
+    resource: &ResourceInput,
+) -> H256 {
+    let packed = [
+        &[0x19, 0x00][..],
+        contract_address.as_bytes(),
+        &u256_as_bytes32(nonce),
+        did_identity.as_bytes(),
+        b"createResource",
+        &keccak256(resource.resource_name.as_bytes()),
+        &keccak256(resource.resource_type.as_bytes()),
+        &keccak256(resource.resource_version_id.as_bytes()),
+        &keccak256(resource.media_type.as_bytes()),
+        &keccak256(&resource.content),
+    ]
+    .concat();
+
+    H256(keccak256(packed))
+}
+
+/// find the [NewResourceFilter] event within the logs of a resource creating transaction
+pub fn decode_new_resource_event(receipt: TransactionReceipt) -> Option<NewResourceFilter> {
+    decode_new_resource_events(receipt).into_iter().next()
+}
+
+/// find all [NewResourceFilter] events within the logs of a resource(s) creating transaction,
+/// in the order they were emitted
+pub fn decode_new_resource_events(receipt: TransactionReceipt) -> Vec<NewResourceFilter> {
+    receipt
+        .logs
+        .into_iter()
+        .filter_map(|log| {
+            let event = NewResourceFilter::decode_log(&RawLog::from(log));
+            event.ok()
+        })
+        .collect()
+}
+
+fn resource_input_data(resource: ResourceInput) -> ResourceInputData {
+    ResourceInputData {
+        resource_name: resource.resource_name,
+        resource_type: resource.resource_type,
+        resource_version: resource.resource_version_id,
+        media_type: resource.media_type,
+        content: resource.content.into(),
+    }
+}
+
+/// decode registry reverts (e.g. "bad_actor") into a [RegistryRevert], so that callers can
+/// handle them explicitly.
+pub(crate) fn decode_contract_error<M: Middleware>(e: ContractError<M>) -> Box<dyn Error> {
+    match e.decode_revert::<String>() {
+        Some(reason) => Box::new(RegistryRevert { reason }),
+        None => anyhow!(e.to_string()).into(),
     }
 }
 
+fn u256_as_bytes32(value: U256) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -191,6 +681,110 @@ mod tests {
     };
 
     use super::EthrDIDLinkedResourcesRegistry;
+    use crate::contracts::registry_version::RegistryVersion;
+
+    #[tokio::test]
+    async fn test_registry_version() {
+        let conf = TestConfig::load();
+        let registry = EthrDIDLinkedResourcesRegistry::new(conf.get_dlr_network_config());
+
+        assert_eq!(
+            registry.registry_version().await.unwrap(),
+            RegistryVersion::V2
+        );
+    }
+
+    /// version 1 registries (deployed before `REGISTRY_VERSION`) can still be read
+    #[cfg(feature = "anvil")]
+    #[tokio::test]
+    async fn test_resolve_from_v1_registry() {
+        use ethers::providers::Middleware;
+
+        use crate::{
+            anvil::{v1_registry::EthrDLRRegistryV1, AnvilNetwork},
+            config::ContractNetworkConfig,
+            resolver::EthrDidLinkedResourcesResolver,
+            types::query::ResourceQuery,
+        };
+
+        let network = AnvilNetwork::start().await.unwrap();
+        let v1_registry_address = network.deploy_v1_dlr_registry().await.unwrap();
+
+        let signer = network.signer(0);
+        let did = did_identity_as_full_did(&signer.address(), network.chain_id());
+
+        let v1_registry = EthrDLRRegistryV1::new(v1_registry_address, signer.clone());
+        for content in ["v1", "v2"] {
+            // a later block time for each version
+            signer
+                .provider()
+                .request::<_, serde_json::Value>("evm_increaseTime", [10])
+                .await
+                .unwrap();
+            v1_registry
+                .create_resource(
+                    signer.address(),
+                    String::from("foo"),
+                    String::from("bar"),
+                    String::from("1.0"),
+                    String::from("text/plain"),
+                    content.as_bytes().to_vec().into(),
+                )
+                .send()
+                .await
+                .unwrap()
+                .await
+                .unwrap();
+        }
+
+        let config = ContractNetworkConfig {
+            rpc_url: network.rpc_url(),
+            contract_address: format!("{v1_registry_address:?}"),
+            chain_id: network.chain_id(),
+        };
+        let registry = EthrDIDLinkedResourcesRegistry::new(config.clone());
+        assert_eq!(
+            registry.registry_version().await.unwrap(),
+            RegistryVersion::V1
+        );
+
+        let resolver = EthrDidLinkedResourcesResolver::new(config);
+
+        // by id (the first resource of the fresh registry)
+        let first = resolver
+            .resolve_query(&format!("{did}/resources/1"))
+            .await
+            .unwrap();
+        assert_eq!(first.content, b"v1");
+        assert!(!first.metadata.deactivated);
+
+        // by name & type, latest and at an epoch
+        let query = ResourceQuery::builder(&did)
+            .unwrap()
+            .resource_name("foo")
+            .resource_type("bar");
+        let latest = resolver
+            .resolve_query(&query.clone().build().to_did_url())
+            .await
+            .unwrap();
+        assert_eq!(latest.content, b"v2");
+        assert_eq!(
+            latest.metadata.previous_version_id,
+            first.metadata.resource_id
+        );
+
+        let at_first = resolver
+            .resolve_query(
+                &query
+                    .version_time(first.metadata.created)
+                    .build()
+                    .to_did_url(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(at_first.metadata.resource_id, first.metadata.resource_id);
+        assert_eq!(at_first.content, b"v1");
+    }
 
     #[tokio::test]
     async fn testtest() {
@@ -214,6 +808,7 @@ mod tests {
                     resource_version_id: String::new(),
                     media_type: String::from("text/plain"),
                     content: "hello world".as_bytes().to_vec(),
+                    content_encoding: None,
                 },
             )
             .await
@@ -231,6 +826,7 @@ mod tests {
                     resource_version_id: String::new(),
                     media_type: String::from("text/plain"),
                     content: "hello world2".as_bytes().to_vec(),
+                    content_encoding: None,
                 },
             )
             .await
@@ -248,6 +844,7 @@ mod tests {
                     resource_version_id: String::new(),
                     media_type: String::from("text/plain"),
                     content: "hello world2".as_bytes().to_vec(),
+                    content_encoding: None,
                 },
             )
             .await
@@ -258,6 +855,7 @@ mod tests {
         let fetched_res1 = registry
             .get_resource_by_id(&did, &resource1.resource.resource_id.to_string())
             .await
+            .unwrap()
             .unwrap();
 
         dbg!(fetched_res1);
@@ -265,6 +863,7 @@ mod tests {
         let fetched_res2 = registry
             .get_resource_by_id(&did, &resource2.resource.resource_id.to_string())
             .await
+            .unwrap()
             .unwrap();
 
         dbg!(fetched_res2);
@@ -272,6 +871,7 @@ mod tests {
         let fetched_res3 = registry
             .get_resource_by_id(&did, &resource3.resource.resource_id.to_string())
             .await
+            .unwrap()
             .unwrap();
 
         dbg!(fetched_res3);
@@ -290,7 +890,8 @@ mod tests {
                         resource_type,
                         epoch_to_try
                     )
-                    .await,
+                    .await
+                    .unwrap(),
             );
         }
     }
@@ -1,37 +1,89 @@
 pub mod eth_did_registry;
 pub mod ethr_dlr_registry;
+pub mod registry_version;
 
 #[cfg(test)]
 pub mod test_utils {
-    use std::{env, sync::Arc};
+    #[cfg(not(feature = "anvil"))]
+    use std::env;
+    use std::sync::Arc;
 
+    #[cfg(not(feature = "anvil"))]
     use dotenv::dotenv;
-    use ethers::{
-        core::k256::ecdsa::SigningKey,
-        middleware::SignerMiddleware,
-        providers::{Http, Provider},
-        signers::{coins_bip39::English, MnemonicBuilder, Signer, Wallet},
-    };
+    #[cfg(not(feature = "anvil"))]
+    use ethers::signers::{coins_bip39::English, MnemonicBuilder, Signer};
+    use ethers::{core::k256::ecdsa::SigningKey, middleware::SignerMiddleware, signers::Wallet};
 
-    use crate::config::ContractNetworkConfig;
+    #[cfg(feature = "anvil")]
+    use crate::anvil::AnvilNetwork;
+    #[cfg(not(feature = "anvil"))]
+    use crate::telemetry;
+    use crate::{config::ContractNetworkConfig, telemetry::HttpProvider};
 
     pub struct TestConfig {
         pub rpc_url: String,
+        /// several RPC endpoints of the same network (`RPC_URLS`, comma separated), defaulting to
+        /// the `rpc_url`
+        pub rpc_urls: Vec<String>,
         pub dlr_contract_address: String,
+        pub did_ethr_contract_address: String,
         pub chain_id: u64,
+        /// the node the config points to, with the `anvil` feature (kept alive by the config)
+        #[cfg(feature = "anvil")]
+        pub network: Arc<AnvilNetwork>,
     }
 
     impl TestConfig {
+        /// Load the network of the `.env`, or (with the `anvil` feature) spawn a fresh node with
+        /// both registries deployed.
+        #[cfg(feature = "anvil")]
+        pub fn load() -> Self {
+            // deploy on a runtime of its own, as this may be called within a (current thread)
+            // test runtime
+            let network = std::thread::spawn(|| {
+                tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap()
+                    .block_on(AnvilNetwork::start())
+                    .map_err(|e| e.to_string())
+            })
+            .join()
+            .unwrap()
+            .unwrap();
+
+            let rpc_url = network.rpc_url();
+            Self {
+                rpc_urls: vec![rpc_url.clone()],
+                rpc_url,
+                dlr_contract_address: format!("{:?}", network.dlr_registry_address()),
+                did_ethr_contract_address: format!("{:?}", network.did_registry_address()),
+                chain_id: network.chain_id(),
+                network: Arc::new(network),
+            }
+        }
+
+        #[cfg(not(feature = "anvil"))]
         pub fn load() -> Self {
             dotenv().ok();
 
             let rpc_url = env::var("RPC_URL").unwrap();
             let dlr_contract_address = env::var("DLR_CONTRACT_ADDRESS").unwrap();
+            let did_ethr_contract_address = env::var("DID_ETHR_CONTRACT_ADDRESS").unwrap();
             let chain_id = env::var("CHAIN_ID").unwrap().parse().unwrap();
+            let rpc_urls = match env::var("RPC_URLS") {
+                Ok(rpc_urls) => rpc_urls
+                    .split(',')
+                    .map(|url| url.trim().to_owned())
+                    .collect(),
+                Err(_) => vec![rpc_url.clone()],
+            };
 
             Self {
                 rpc_url,
+                rpc_urls,
                 dlr_contract_address,
+                did_ethr_contract_address,
                 chain_id,
             }
         }
@@ -43,12 +95,36 @@ pub mod test_utils {
                 chain_id: self.chain_id,
             }
         }
+
+        pub fn get_did_ethr_network_config(&self) -> ContractNetworkConfig {
+            ContractNetworkConfig {
+                rpc_url: self.rpc_url.clone(),
+                contract_address: self.did_ethr_contract_address.clone(),
+                chain_id: self.chain_id,
+            }
+        }
     }
 
     pub fn get_writer_ethers_client(
         id: u32,
         conf: &TestConfig,
-    ) -> Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>> {
+    ) -> Arc<SignerMiddleware<HttpProvider, Wallet<SigningKey>>> {
+        #[cfg(feature = "anvil")]
+        {
+            conf.network.signer(id as usize)
+        }
+
+        #[cfg(not(feature = "anvil"))]
+        {
+            writer_from_mnemonic(id, conf)
+        }
+    }
+
+    #[cfg(not(feature = "anvil"))]
+    fn writer_from_mnemonic(
+        id: u32,
+        conf: &TestConfig,
+    ) -> Arc<SignerMiddleware<HttpProvider, Wallet<SigningKey>>> {
         dotenv().ok();
 
         let seed = env::var("MNEMONIC").unwrap();
@@ -61,7 +137,7 @@ pub mod test_utils {
             .unwrap()
             .with_chain_id(conf.chain_id);
 
-        let provider = Provider::<Http>::try_from(&conf.rpc_url).unwrap();
+        let provider = telemetry::http_provider(&conf.rpc_url).unwrap();
         Arc::new(SignerMiddleware::new(provider, wallet))
     }
 }
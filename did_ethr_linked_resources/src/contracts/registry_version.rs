@@ -0,0 +1,264 @@
+use std::{error::Error, sync::Arc};
+
+use ethers::{
+    abi::{encode, Token},
+    providers::Middleware,
+    types::{Address, H160, H256, U256},
+    utils::keccak256,
+};
+
+use super::ethr_dlr_registry::{
+    decode_contract_error, EthrDLRRegistry, LedgerTime, ResourceVersionMetadataChainNode,
+};
+
+/// Bindings for version 1 registries. Only the functions whose ABI differs from the latest
+/// registry are included (the `getResourceMetadataChain*` views, keyed by the concatenated name &
+/// type, see `abi/EthrDIDLinkedResourcesRegistryV1.json`), the rest are called with the latest
+/// bindings.
+mod v1 {
+    include!(concat!(
+        env!("OUT_DIR"),
+        "/ethr_dlr_registry_v1_contract.rs"
+    ));
+}
+
+impl From<v1::ResourceVersionMetadataChainNode> for ResourceVersionMetadataChainNode {
+    fn from(value: v1::ResourceVersionMetadataChainNode) -> Self {
+        Self {
+            resource_id: value.resource_id,
+            created: LedgerTime {
+                block_timestamp: value.created.block_timestamp,
+                block_number: value.created.block_number,
+            },
+            next_resource_id: value.next_resource_id,
+            previous_resource_id: value.previous_resource_id,
+        }
+    }
+}
+
+/// Version of a deployed registry contract (its `REGISTRY_VERSION`), which determines the
+/// bindings used to call it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryVersion {
+    /// Registries deployed before versioning (without `REGISTRY_VERSION`). Resources are keyed by
+    /// their concatenated name & type, which is ambiguous (e.g. `("ab", "c")` and `("a", "bc")`
+    /// are the same resource).
+    V1,
+    /// Resources are keyed by `keccak256(abi.encode(name, type))`.
+    V2,
+}
+
+impl RegistryVersion {
+    /// the value which the registry hashes to key the resource, also the `NewResource` event
+    /// parameter which is indexed as [Self::resource_topic]
+    pub(crate) fn resource_key_preimage(
+        &self,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Vec<u8> {
+        match self {
+            RegistryVersion::V1 => format!("{}{}", resource_name, resource_type).into_bytes(),
+            RegistryVersion::V2 => encode(&[
+                Token::String(resource_name.to_owned()),
+                Token::String(resource_type.to_owned()),
+            ]),
+        }
+    }
+
+    /// the event topic of the resource, for filtering its `NewResource` events
+    pub fn resource_topic(&self, resource_name: &str, resource_type: &str) -> H256 {
+        H256(keccak256(
+            self.resource_key_preimage(resource_name, resource_type),
+        ))
+    }
+}
+
+impl TryFrom<U256> for RegistryVersion {
+    type Error = Box<dyn Error>;
+
+    fn try_from(value: U256) -> Result<Self, Self::Error> {
+        match u64::try_from(value) {
+            Ok(2) => Ok(RegistryVersion::V2),
+            _ => Err(format!("Unsupported registry version: {value}").into()),
+        }
+    }
+}
+
+/// The `getResourceKey` of a [RegistryVersion::V2] registry.
+pub fn resource_key(resource_name: &str, resource_type: &str) -> [u8; 32] {
+    RegistryVersion::V2
+        .resource_topic(resource_name, resource_type)
+        .0
+}
+
+/// Detect the version of the registry deployed at `address`.
+pub(crate) async fn detect_registry_version<M: Middleware>(
+    address: Address,
+    client: Arc<M>,
+) -> Result<RegistryVersion, Box<dyn Error>> {
+    match EthrDLRRegistry::new(address, client)
+        .registry_version()
+        .call()
+        .await
+    {
+        Ok(version) => RegistryVersion::try_from(version),
+        // version 1 registries have no `REGISTRY_VERSION`
+        Err(e) if e.is_revert() => Ok(RegistryVersion::V1),
+        Err(e) => Err(decode_contract_error(e)),
+    }
+}
+
+/// The bindings of a deployed registry, matching its [RegistryVersion]. Dispatches the registry
+/// functions whose ABI differs between versions.
+pub(crate) enum VersionedRegistry<M> {
+    V1(v1::EthrDLRRegistryV1<M>),
+    V2(EthrDLRRegistry<M>),
+}
+
+impl<M: Middleware> VersionedRegistry<M> {
+    pub(crate) fn new(version: RegistryVersion, address: Address, client: Arc<M>) -> Self {
+        match version {
+            RegistryVersion::V1 => Self::V1(v1::EthrDLRRegistryV1::new(address, client)),
+            RegistryVersion::V2 => Self::V2(EthrDLRRegistry::new(address, client)),
+        }
+    }
+
+    pub(crate) async fn is_resource_deactivated(
+        &self,
+        did_identity: H160,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        match self {
+            // version 1 registries can't deactivate resources
+            Self::V1(_) => Ok(false),
+            Self::V2(contract) => contract
+                .is_resource_deactivated(did_identity, resource_key(resource_name, resource_type))
+                .call()
+                .await
+                .map_err(decode_contract_error),
+        }
+    }
+
+    pub(crate) async fn get_resource_metadata_chain(
+        &self,
+        did_identity: H160,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<Vec<ResourceVersionMetadataChainNode>, Box<dyn Error>> {
+        match self {
+            Self::V1(contract) => {
+                let metadata_chain = contract
+                    .get_resource_metadata_chain(
+                        did_identity,
+                        format!("{}{}", resource_name, resource_type),
+                    )
+                    .call()
+                    .await
+                    .map_err(decode_contract_error)?;
+                Ok(metadata_chain.into_iter().map(Into::into).collect())
+            }
+            Self::V2(contract) => contract
+                .get_resource_metadata_chain(
+                    did_identity,
+                    resource_key(resource_name, resource_type),
+                )
+                .call()
+                .await
+                .map_err(decode_contract_error),
+        }
+    }
+
+    /// the latest node of the resource's metadata chain, if it has any versions
+    pub(crate) async fn get_resource_metadata_chain_latest_node(
+        &self,
+        did_identity: H160,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<Option<ResourceVersionMetadataChainNode>, Box<dyn Error>> {
+        let latest_node = match self {
+            Self::V1(contract) => contract
+                .get_resource_metadata_chain_latest_node(
+                    did_identity,
+                    format!("{}{}", resource_name, resource_type),
+                )
+                .call()
+                .await
+                .map(Into::into),
+            Self::V2(contract) => {
+                contract
+                    .get_resource_metadata_chain_latest_node(
+                        did_identity,
+                        resource_key(resource_name, resource_type),
+                    )
+                    .call()
+                    .await
+            }
+        };
+        match latest_node {
+            Ok(latest_node) => Ok(Some(latest_node)),
+            // the registry reverts (underflowing the index) if the chain is empty
+            Err(e) if e.is_revert() => Ok(None),
+            Err(e) => Err(decode_contract_error(e)),
+        }
+    }
+
+    pub(crate) async fn get_resource_metadata_chain_node(
+        &self,
+        did_identity: H160,
+        resource_name: &str,
+        resource_type: &str,
+        index: u64,
+    ) -> Result<ResourceVersionMetadataChainNode, Box<dyn Error>> {
+        match self {
+            Self::V1(contract) => contract
+                .get_resource_metadata_chain_node(
+                    did_identity,
+                    format!("{}{}", resource_name, resource_type),
+                    U256::from(index),
+                )
+                .call()
+                .await
+                .map(Into::into)
+                .map_err(decode_contract_error),
+            Self::V2(contract) => contract
+                .get_resource_metadata_chain_node(
+                    did_identity,
+                    resource_key(resource_name, resource_type),
+                    U256::from(index),
+                )
+                .call()
+                .await
+                .map_err(decode_contract_error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use super::RegistryVersion;
+
+    #[test]
+    fn test_resource_topics() {
+        let v1_topic =
+            |name, resource_type| RegistryVersion::V1.resource_topic(name, resource_type);
+        let v2_topic =
+            |name, resource_type| RegistryVersion::V2.resource_topic(name, resource_type);
+
+        assert_eq!(v1_topic("ab", "c"), v1_topic("a", "bc"));
+        assert_ne!(v2_topic("ab", "c"), v2_topic("a", "bc"));
+        assert_ne!(v2_topic("ab", "c"), v1_topic("ab", "c"));
+    }
+
+    #[test]
+    fn test_registry_version_from_number() {
+        assert_eq!(
+            RegistryVersion::try_from(U256::from(2)).unwrap(),
+            RegistryVersion::V2
+        );
+        assert!(RegistryVersion::try_from(U256::from(3)).is_err());
+        assert!(RegistryVersion::try_from(U256::MAX).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{error::Error, fmt};
+
+use ethers::types::H160;
+
+/// Errors from writing resources via the [crate::registrar::EthrDidLinkedResourcesRegistrar].
+#[derive(Debug)]
+pub enum RegistrarError {
+    /// The signer is not authorized to write resources for the DID, i.e. it is not the
+    /// current controller of the DID (nor a valid delegate, when writing as a delegate).
+    Unauthorized {
+        did: String,
+        /// the signer's address, if the client has a default sender
+        signer: Option<H160>,
+        current_controller: H160,
+    },
+    /// The resource version id is not permitted by the registrar's
+    /// [crate::config::VersionPolicy].
+    InvalidVersion(String),
+    /// The resource input is invalid, or is ambiguous with an existing resource of the DID.
+    InvalidInput(ResourceInputError),
+    /// Any other failure, e.g. RPC or transaction errors.
+    Other(Box<dyn Error>),
+}
+
+impl fmt::Display for RegistrarError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistrarError::Unauthorized {
+                did,
+                signer,
+                current_controller,
+            } => match signer {
+                Some(signer) => write!(
+                    f,
+                    "Unauthorized: {signer:?} is not the controller of {did} (current controller: {current_controller:?})"
+                ),
+                None => write!(
+                    f,
+                    "Unauthorized: the signer is not the controller of {did} (current controller: {current_controller:?})"
+                ),
+            },
+            RegistrarError::InvalidVersion(reason) => write!(f, "Invalid version: {reason}"),
+            RegistrarError::InvalidInput(e) => write!(f, "{e}"),
+            RegistrarError::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for RegistrarError {}
+
+impl From<Box<dyn Error>> for RegistrarError {
+    fn from(value: Box<dyn Error>) -> Self {
+        RegistrarError::Other(value)
+    }
+}
+
+impl From<ResourceInputError> for RegistrarError {
+    fn from(value: ResourceInputError) -> Self {
+        RegistrarError::InvalidInput(value)
+    }
+}
+
+/// Errors from validating a [crate::types::input::ResourceInput], see
+/// [crate::types::input::ResourceInput::builder].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceInputError {
+    /// The resource name is empty, too long, or contains unsupported characters.
+    InvalidName(String),
+    /// The resource type is empty, too long, or contains unsupported characters.
+    InvalidType(String),
+    /// The media type is not a valid MIME type.
+    InvalidMediaType(String),
+    /// The resource name & type concatenate to the same value as those of another resource
+    /// (e.g. `("ab", "c")` and `("a", "bc")`), which the registry would treat as the same
+    /// resource.
+    AmbiguousNameAndType {
+        existing_name: String,
+        existing_type: String,
+    },
+}
+
+impl fmt::Display for ResourceInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceInputError::InvalidName(reason) => {
+                write!(f, "Invalid resource name: {reason}")
+            }
+            ResourceInputError::InvalidType(reason) => {
+                write!(f, "Invalid resource type: {reason}")
+            }
+            ResourceInputError::InvalidMediaType(reason) => {
+                write!(f, "Invalid media type: {reason}")
+            }
+            ResourceInputError::AmbiguousNameAndType {
+                existing_name,
+                existing_type,
+            } => write!(
+                f,
+                "Resource name & type are ambiguous with existing resource {existing_name:?} {existing_type:?}"
+            ),
+        }
+    }
+}
+
+impl Error for ResourceInputError {}
+
+/// Errors from parsing a DID URL, see [crate::types::query::ResourceQuery::parse_from_str].
+#[derive(Debug, Clone, PartialEq)]
+pub enum DidUrlError {
+    /// The DID URL does not start with a DID (`did:<method name>:<method specific id>`).
+    InvalidDid(String),
+    /// A character which is not permitted in that part of the DID URL, at the byte `position`.
+    InvalidCharacter { position: usize, character: char },
+    /// A `%` which is not followed by two hex digits, or percent-encoded bytes which are not
+    /// UTF-8, at the byte `position`.
+    InvalidPercentEncoding { position: usize },
+    /// The DID method is not `ethr`.
+    UnsupportedMethod(String),
+    /// The method specific identifier is not an (optional) network and an Ethereum address.
+    InvalidIdentifier(String),
+    /// The path is not empty, nor `/resources/<resource id>`.
+    InvalidPath(String),
+    /// A query parameter has an invalid value, or is repeated.
+    InvalidParameter { name: String, reason: String },
+}
+
+impl fmt::Display for DidUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DidUrlError::InvalidDid(reason) => write!(f, "Invalid DID: {reason}"),
+            DidUrlError::InvalidCharacter {
+                position,
+                character,
+            } => write!(f, "Invalid character {character:?} at {position}"),
+            DidUrlError::InvalidPercentEncoding { position } => {
+                write!(f, "Invalid percent-encoding at {position}")
+            }
+            DidUrlError::UnsupportedMethod(method) => {
+                write!(f, "Unsupported DID method {method:?}, expected \"ethr\"")
+            }
+            DidUrlError::InvalidIdentifier(reason) => {
+                write!(f, "Invalid did:ethr identifier: {reason}")
+            }
+            DidUrlError::InvalidPath(path) => write!(f, "Invalid DID URL path {path:?}"),
+            DidUrlError::InvalidParameter { name, reason } => {
+                write!(f, "Invalid query parameter {name:?}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for DidUrlError {}
+
+/// A call or transaction which was reverted by the registry contract, with the revert reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryRevert {
+    pub reason: String,
+}
+
+impl RegistryRevert {
+    /// Whether the revert was due to the registry's DID controller (or signature) checks.
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self.reason.as_str(), "bad_actor" | "bad_signature")
+    }
+}
+
+impl fmt::Display for RegistryRevert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Registry reverted: {}", self.reason)
+    }
+}
+
+impl Error for RegistryRevert {}
+
+/// Errors from decoding the content of a resource, see [crate::resource_content].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentDecodeError {
+    /// The resource is not of a media type which the content can be decoded from.
+    MediaTypeMismatch {
+        expected: String,
+        media_type: String,
+    },
+    /// The media type of the resource is not a valid MIME type.
+    InvalidMediaType(String),
+    /// The content is not valid for its media type.
+    Malformed(String),
+}
+
+impl fmt::Display for ContentDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContentDecodeError::MediaTypeMismatch {
+                expected,
+                media_type,
+            } => write!(
+                f,
+                "Media type mismatch: expected {expected}, got {media_type:?}"
+            ),
+            ContentDecodeError::InvalidMediaType(reason) => {
+                write!(f, "Invalid media type: {reason}")
+            }
+            ContentDecodeError::Malformed(reason) => write!(f, "Malformed content: {reason}"),
+        }
+    }
+}
+
+impl Error for ContentDecodeError {}
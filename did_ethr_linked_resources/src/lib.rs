@@ -1,22 +1,49 @@
+#[cfg(feature = "anvil")]
+pub mod anvil;
+pub mod chunking;
 pub mod config;
+pub mod content_encoding;
+pub mod content_store;
 pub mod contracts;
+pub mod error;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod multi_rpc;
+mod nonce_manager;
 pub mod registrar;
 pub mod resolver;
+pub mod resource_content;
 #[cfg(feature = "thegraph")]
 mod subgraph;
+pub mod telemetry;
 pub mod types;
 pub mod utils;
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use chrono::{TimeZone, Utc};
+    use ethers::types::U256;
 
     use crate::{
-        contracts::test_utils::{get_writer_ethers_client, TestConfig},
-        registrar::EthrDidLinkedResourcesRegistrar,
-        resolver::EthrDidLinkedResourcesResolver,
-        types::input::ResourceInput,
-        utils::did_identity_as_full_did,
+        chunking::ChunkedUpload,
+        config::{FeeStrategy, PriorityFeePolicy, VersionPolicy},
+        content_encoding::ContentEncoding,
+        content_store::file_system::FileSystemContentStore,
+        contracts::{
+            eth_did_registry::DidEthRegistry,
+            test_utils::{get_writer_ethers_client, TestConfig},
+        },
+        error::RegistrarError,
+        registrar::{CreateResourceOptions, EthrDidLinkedResourcesRegistrar},
+        resolver::{EthrDidLinkedResourcesResolver, ResolutionOptions, VersionSelection},
+        types::{
+            input::{ResourceInput, SignedResourceInput},
+            output::PendingResource,
+            query::ResourceQuery,
+        },
+        utils::{did_identity_as_full_did, VERI_KEY_DELEGATE_TYPE},
     };
 
     #[tokio::test]
@@ -68,16 +95,887 @@ mod tests {
         let epoch_range = created_res1.metadata.created.timestamp() - 4
             ..created_res3.metadata.created.timestamp() + 4;
         for epoch in epoch_range.step_by(2) {
-            // get time as DateTime string
             let datetime = Utc.timestamp_opt(epoch, 0).unwrap();
-            let formatted_dt = datetime.to_rfc3339();
-            let version_time = urlencoding::encode(&formatted_dt);
-            let query = format!(
-                "{did}?resourceName={resource_name}&resourceType={resource_type}&versionTime={version_time}"
+            let query = ResourceQuery::builder(&did)
+                .unwrap()
+                .resource_name(resource_name)
+                .resource_type(resource_type)
+                .version_time(datetime)
+                .build();
+
+            let resolved_res = resolver.resolve_query(&query.to_did_url()).await.ok();
+            dbg!(datetime, resolved_res);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delegate_can_create_resource() {
+        let conf = TestConfig::load();
+
+        let controller = get_writer_ethers_client(3, &conf);
+        let delegate = get_writer_ethers_client(4, &conf);
+        let did = did_identity_as_full_did(&controller.address(), conf.chain_id);
+
+        let did_registry = DidEthRegistry::new(conf.get_did_ethr_network_config());
+        let delegate_registrar = EthrDidLinkedResourcesRegistrar::new_as_delegate(
+            delegate.clone(),
+            conf.get_dlr_network_config(),
+            VERI_KEY_DELEGATE_TYPE,
+        );
+
+        let res_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: String::from("hello world").into_bytes(),
+            ..Default::default()
+        };
+
+        // not yet a delegate
+        assert!(delegate_registrar
+            .create_resource(&did, res_input.clone())
+            .await
+            .is_err());
+
+        did_registry
+            .add_delegate(
+                controller.clone(),
+                &did,
+                VERI_KEY_DELEGATE_TYPE,
+                delegate.address(),
+                3600,
+            )
+            .await;
+
+        let created_res = delegate_registrar
+            .create_resource(&did, res_input.clone())
+            .await
+            .unwrap();
+        assert_eq!(created_res.content, res_input.content);
+
+        // a delegate cannot write as if it were the controller
+        let non_delegate_registrar =
+            EthrDidLinkedResourcesRegistrar::new(delegate.clone(), conf.get_dlr_network_config());
+        let err = non_delegate_registrar
+            .create_resource(&did, res_input.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RegistrarError::Unauthorized { signer, current_controller, .. }
+                if signer == Some(delegate.address()) && current_controller == controller.address()
+        ));
+
+        did_registry
+            .revoke_delegate(controller, &did, VERI_KEY_DELEGATE_TYPE, delegate.address())
+            .await;
+
+        assert!(delegate_registrar
+            .create_resource(&did, res_input)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_relayer_can_submit_signed_resource() {
+        let conf = TestConfig::load();
+
+        let controller = get_writer_ethers_client(5, &conf);
+        let relayer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&controller.address(), conf.chain_id);
+
+        let relaying_registrar =
+            EthrDidLinkedResourcesRegistrar::new(relayer, conf.get_dlr_network_config());
+
+        let res_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: String::from("hello world").into_bytes(),
+            ..Default::default()
+        };
+
+        // controller signs offline
+        let nonce = relaying_registrar
+            .get_signed_write_nonce(controller.address())
+            .await
+            .unwrap();
+        let signed_input = SignedResourceInput::sign(
+            controller.signer(),
+            &conf.get_dlr_network_config(),
+            &did,
+            nonce,
+            res_input.clone(),
+        )
+        .unwrap();
+
+        let created_res = relaying_registrar
+            .create_resource_signed(signed_input.clone())
+            .await
+            .unwrap();
+        assert_eq!(created_res.content, res_input.content);
+        assert!(created_res.metadata.resource_uri.starts_with(&did));
+
+        // signature cannot be replayed
+        assert!(relaying_registrar
+            .create_resource_signed(signed_input)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_and_dry_run_resource() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let other_signer = get_writer_ethers_client(6, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let mut registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        registrar.set_fee_strategy(FeeStrategy::Eip1559 {
+            max_fee_per_gas_cap: None,
+            priority_fee: PriorityFeePolicy::Percentage(150),
+        });
+        registrar.set_dry_run_before_send(true);
+
+        let small_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: vec![1; 32],
+            ..Default::default()
+        };
+        let large_input = ResourceInput {
+            content: vec![1; 4096],
+            ..small_input.clone()
+        };
+
+        let small_estimate = registrar
+            .estimate_resource_cost(&did, &small_input)
+            .await
+            .unwrap();
+        let large_estimate = registrar
+            .estimate_resource_cost(&did, &large_input)
+            .await
+            .unwrap();
+        assert!(small_estimate.gas < large_estimate.gas);
+        assert_eq!(
+            large_estimate.max_cost_wei,
+            large_estimate.gas * large_estimate.max_fee_per_gas
+        );
+
+        registrar
+            .dry_run_create_resource(&did, &small_input)
+            .await
+            .unwrap();
+        registrar
+            .create_resource(&did, small_input.clone())
+            .await
+            .unwrap();
+
+        // dry run as a non-controller
+        registrar.change_signer(other_signer);
+        let err = registrar
+            .dry_run_create_resource(&did, &small_input)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RegistrarError::Unauthorized { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_resume_pending_resource() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer.clone(), conf.get_dlr_network_config());
+
+        let resource_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+        let pending = registrar
+            .submit_resource(&did, resource_input.clone())
+            .await
+            .unwrap();
+
+        // persist and resume, as if after a restart
+        let persisted = serde_json::to_string(&pending).unwrap();
+        let resumed: PendingResource = serde_json::from_str(&persisted).unwrap();
+        let resumed_registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        let resource = resumed_registrar
+            .await_resource(resumed.tx_hash)
+            .await
+            .unwrap();
+        assert_eq!(resource.content, resource_input.content);
+        assert_eq!(
+            resource.metadata.resource_name,
+            resource_input.resource_name
+        );
+
+        // already mined, so cannot be replaced
+        assert!(resumed_registrar
+            .bump_resource_fee(&resumed, 20)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_resources_in_batch() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+
+        let resource_name = format!("foo{}", uuid::Uuid::new_v4());
+        let resource_inputs: Vec<_> = (0..3)
+            .map(|i| ResourceInput {
+                resource_name: resource_name.clone(),
+                resource_type: String::from("bar"),
+                content: vec![i; 4],
+                ..Default::default()
+            })
+            .collect();
+
+        let resources = registrar
+            .create_resources(&did, resource_inputs.clone())
+            .await
+            .unwrap();
+        assert_eq!(resources.len(), 3);
+        for (resource, input) in resources.iter().zip(&resource_inputs) {
+            assert_eq!(resource.content, input.content);
+        }
+
+        // all written in the same block, as versions of the same resource
+        let created = resources[0].metadata.created;
+        assert!(resources.iter().all(|r| r.metadata.created == created));
+        assert_eq!(
+            resources[1].metadata.previous_version_id,
+            resources[0].metadata.resource_id
+        );
+
+        let resolved = resolver
+            .resolve_query(&resources[0].metadata.resource_uri)
+            .await
+            .unwrap();
+        assert_eq!(resolved.content, resource_inputs[0].content);
+
+        // by name & type at the batch's time: the last version written in the block
+        let query = ResourceQuery::builder(&did)
+            .unwrap()
+            .resource_name(&resource_name)
+            .resource_type("bar")
+            .version_time(created)
+            .build()
+            .to_did_url();
+        let resolved = resolver.resolve_query(&query).await.unwrap();
+        assert_eq!(
+            resolved.metadata.resource_id,
+            resources[2].metadata.resource_id
+        );
+        assert_eq!(resolved.content, resource_inputs[2].content);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_from_one_signer() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let mut registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+
+        let resource_input = |i: u8| ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: vec![i; 4],
+            ..Default::default()
+        };
+
+        let (res1, res2, res3) = tokio::join!(
+            registrar.create_resource(&did, resource_input(1)),
+            registrar.create_resource(&did, resource_input(2)),
+            registrar.create_resource(&did, resource_input(3)),
+        );
+        assert_eq!(res1.unwrap().content, vec![1; 4]);
+        assert_eq!(res2.unwrap().content, vec![2; 4]);
+        assert_eq!(res3.unwrap().content, vec![3; 4]);
+
+        // a failed write (unauthorized) does not block later writes
+        let other_did =
+            did_identity_as_full_did(&get_writer_ethers_client(7, &conf).address(), conf.chain_id);
+        let (unauthorized, res) = tokio::join!(
+            registrar.create_resource(&other_did, resource_input(4)),
+            registrar.create_resource(&did, resource_input(5)),
+        );
+        assert!(unauthorized.is_err());
+        assert_eq!(res.unwrap().content, vec![5; 4]);
+
+        registrar.resync_nonce();
+        registrar
+            .create_resource(&did, resource_input(6))
+            .await
+            .unwrap();
+
+        // nor does a write which fails after its nonce is allocated (its fees can't be paid)
+        registrar.set_fee_strategy(FeeStrategy::Eip1559 {
+            max_fee_per_gas_cap: None,
+            priority_fee: PriorityFeePolicy::Fixed(U256::exp10(30)),
+        });
+        assert!(registrar
+            .create_resource(&did, resource_input(7))
+            .await
+            .is_err());
+        registrar.set_fee_strategy(FeeStrategy::ProviderDefault);
+        let res = registrar.create_resource(&did, resource_input(8)).await;
+        assert_eq!(res.unwrap().content, vec![8; 4]);
+    }
+
+    #[tokio::test]
+    async fn test_create_resource_with_off_chain_content() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let directory = std::env::temp_dir().join(uuid::Uuid::new_v4().to_string());
+        let store = Arc::new(FileSystemContentStore::new(&directory));
+
+        let mut registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        registrar.set_content_store(store.clone());
+
+        let resource_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            media_type: String::from("text/plain"),
+            content: "hello world".as_bytes().to_vec(),
+            ..Default::default()
+        };
+        let resource = registrar
+            .create_resource(&did, resource_input.clone())
+            .await
+            .unwrap();
+        assert_eq!(resource.content, resource_input.content);
+        assert_eq!(resource.metadata.media_type, "text/plain");
+
+        // only the reference is on chain
+        let mut resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let on_chain = resolver
+            .resolve_query(&resource.metadata.resource_uri)
+            .await;
+        assert!(on_chain.is_err());
+
+        resolver.add_content_store(store);
+        let resolved = resolver
+            .resolve_query(&resource.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert_eq!(resolved, resource);
+
+        // tampered content is rejected
+        for file in std::fs::read_dir(&directory).unwrap() {
+            std::fs::write(file.unwrap().path(), "goodbye world").unwrap();
+        }
+        assert!(resolver
+            .resolve_query(&resource.metadata.resource_uri)
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_resource_with_content_encoding() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+
+        let content = r#"{"foo": "bar"}"#.repeat(100).into_bytes();
+        for content_encoding in [ContentEncoding::Zstd, ContentEncoding::Deflate] {
+            let resource_input = ResourceInput {
+                resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+                resource_type: String::from("bar"),
+                media_type: String::from("application/json"),
+                content: content.clone(),
+                content_encoding: Some(content_encoding),
+                ..Default::default()
+            };
+            let estimate = registrar
+                .estimate_resource_cost(&did, &resource_input)
+                .await
+                .unwrap();
+            let uncompressed_estimate = registrar
+                .estimate_resource_cost(
+                    &did,
+                    &ResourceInput {
+                        content_encoding: None,
+                        ..resource_input.clone()
+                    },
+                )
+                .await
+                .unwrap();
+            assert!(estimate.gas < uncompressed_estimate.gas);
+
+            let resource = registrar
+                .create_resource(&did, resource_input)
+                .await
+                .unwrap();
+            assert_eq!(resource.content, content);
+            assert_eq!(resource.metadata.media_type, "application/json");
+            assert_eq!(resource.metadata.content_encoding, Some(content_encoding));
+
+            let resolved = resolver
+                .resolve_query(&resource.metadata.resource_uri)
+                .await
+                .unwrap();
+            assert_eq!(resolved, resource);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_resource_chunked() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+
+        let resource_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            media_type: String::from("application/octet-stream"),
+            content: (0..3000).map(|i| (i % 256) as u8).collect(),
+            ..Default::default()
+        };
+
+        let mut upload = ChunkedUpload::default();
+        let mut progress = vec![];
+        let resource = registrar
+            .create_resource_chunked(&did, resource_input.clone(), 1024, &mut upload, |upload| {
+                progress.push(upload.written_chunk_ids.len())
+            })
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![1, 2, 3]);
+        assert!(upload.is_complete());
+        assert_eq!(resource.content, resource_input.content);
+        assert_eq!(resource.metadata.media_type, "application/octet-stream");
+
+        let resolved = resolver
+            .resolve_query(&resource.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert_eq!(resolved, resource);
+
+        // resume an upload which only wrote the first chunk
+        let mut partial_upload = ChunkedUpload {
+            written_chunk_ids: upload.written_chunk_ids[..1].to_vec(),
+            ..upload.clone()
+        };
+        let mut progress = vec![];
+        let resumed = registrar
+            .create_resource_chunked(
+                &did,
+                resource_input.clone(),
+                1024,
+                &mut partial_upload,
+                |upload| progress.push(upload.written_chunk_ids.len()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![2, 3]);
+        assert_eq!(
+            partial_upload.written_chunk_ids[0],
+            upload.written_chunk_ids[0]
+        );
+        assert_eq!(resumed.content, resource_input.content);
+
+        // cannot resume with different content
+        let mut upload_of_other_content = ChunkedUpload {
+            written_chunk_ids: upload.written_chunk_ids[..1].to_vec(),
+            ..upload
+        };
+        let res = registrar
+            .create_resource_chunked(
+                &did,
+                ResourceInput {
+                    content: vec![1; 3000],
+                    ..resource_input
+                },
+                1024,
+                &mut upload_of_other_content,
+                |_| {},
+            )
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_resource() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+
+        let resource_name = format!("foo{}", uuid::Uuid::new_v4());
+        let resource_type = "bar";
+        let query = format!("{did}?resourceName={resource_name}&resourceType={resource_type}");
+
+        // cannot deactivate a resource which does not exist
+        assert!(registrar
+            .deactivate_resource(&did, &resource_name, resource_type)
+            .await
+            .is_err());
+
+        let resource = registrar
+            .create_resource(
+                &did,
+                ResourceInput {
+                    resource_name: resource_name.clone(),
+                    resource_type: resource_type.to_owned(),
+                    content: vec![1, 2, 3, 4],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(!resource.metadata.deactivated);
+
+        let tombstone = registrar
+            .deactivate_resource(&did, &resource_name, resource_type)
+            .await
+            .unwrap();
+        assert!(tombstone.metadata.deactivated);
+        assert_eq!(
+            tombstone.metadata.previous_version_id,
+            resource.metadata.resource_id
+        );
+
+        // latest resolution is refused
+        assert!(resolver.resolve_query(&query).await.is_err());
+
+        // unless history is requested
+        let options = ResolutionOptions {
+            include_deactivated: true,
+            ..Default::default()
+        };
+        let resolved = resolver
+            .resolve_query_with_options(&query, &options)
+            .await
+            .unwrap();
+        assert_eq!(resolved.content, resource.content);
+        assert!(resolved.metadata.deactivated);
+
+        // exact versions can still be resolved, as of their creation
+        let resolved = resolver
+            .resolve_query(&resource.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert!(!resolved.metadata.deactivated);
+        let resolved = resolver
+            .resolve_query(&tombstone.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert!(resolved.metadata.deactivated);
+
+        // a new version reactivates the resource
+        registrar
+            .create_resource(
+                &did,
+                ResourceInput {
+                    resource_name: resource_name.clone(),
+                    resource_type: resource_type.to_owned(),
+                    content: vec![5, 6, 7, 8],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        let resolved = resolver.resolve_query(&query).await.unwrap();
+        assert_eq!(resolved.content, vec![5, 6, 7, 8]);
+        assert!(!resolved.metadata.deactivated);
+    }
+
+    #[tokio::test]
+    async fn test_create_resource_if_changed() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        let options = CreateResourceOptions { if_changed: true };
+
+        let resource_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            resource_version_id: String::from("1.0"),
+            media_type: String::from("text/plain"),
+            content: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+
+        let resource = registrar
+            .create_resource_with_options(&did, resource_input.clone(), &options)
+            .await
+            .unwrap();
+
+        // identical, so not rewritten
+        let unchanged = registrar
+            .create_resource_with_options(&did, resource_input.clone(), &options)
+            .await
+            .unwrap();
+        assert_eq!(unchanged, resource);
+
+        // any of content, media type or version id changing writes a new version
+        let changed_inputs = [
+            ResourceInput {
+                content: vec![5, 6, 7, 8],
+                ..resource_input.clone()
+            },
+            ResourceInput {
+                media_type: String::from("application/octet-stream"),
+                ..resource_input.clone()
+            },
+            ResourceInput {
+                resource_version_id: String::from("2.0"),
+                ..resource_input.clone()
+            },
+        ];
+        let mut latest = resource;
+        for changed_input in changed_inputs {
+            let changed = registrar
+                .create_resource_with_options(&did, changed_input, &options)
+                .await
+                .unwrap();
+            assert_eq!(
+                changed.metadata.previous_version_id,
+                latest.metadata.resource_id
             );
+            latest = changed;
+        }
+
+        // without the option, identical content is rewritten
+        let rewritten = registrar
+            .create_resource(&did, resource_input.clone())
+            .await
+            .unwrap();
+        let rewritten_again = registrar
+            .create_resource(&did, resource_input)
+            .await
+            .unwrap();
+        assert_ne!(
+            rewritten.metadata.resource_id,
+            rewritten_again.metadata.resource_id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_version_policies() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let mut registrar =
+            EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+
+        let resource_name = format!("foo{}", uuid::Uuid::new_v4());
+        let resource_type = "bar";
+        let query = format!("{did}?resourceName={resource_name}&resourceType={resource_type}");
+        let input = |version: &str, content: u8| ResourceInput {
+            resource_name: resource_name.clone(),
+            resource_type: resource_type.to_owned(),
+            resource_version_id: version.to_owned(),
+            content: vec![content],
+            ..Default::default()
+        };
+
+        registrar.set_version_policy(VersionPolicy::Unique);
+        registrar
+            .create_resource(&did, input("2.0.0", 1))
+            .await
+            .unwrap();
+        let err = registrar
+            .create_resource(&did, input("2.0.0", 2))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RegistrarError::InvalidVersion(_)));
 
-            let resolved_res = resolver.resolve_query(&query).await.ok();
-            dbg!(formatted_dt, resolved_res);
+        registrar.set_version_policy(VersionPolicy::Semver);
+        for version in ["not-semver", "1.0.0", "2.0.0"] {
+            let err = registrar
+                .create_resource(&did, input(version, 2))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, RegistrarError::InvalidVersion(_)));
         }
+        // versions within a batch are checked against each other too
+        let err = registrar
+            .create_resources(&did, vec![input("3.0.0", 3), input("2.5.0", 4)])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RegistrarError::InvalidVersion(_)));
+        registrar
+            .create_resource(&did, input("3.0.0", 3))
+            .await
+            .unwrap();
+
+        // a patch to an older major, permitted without a policy, is not the highest semver
+        registrar.set_version_policy(VersionPolicy::Any);
+        registrar
+            .create_resource(&did, input("2.0.1", 4))
+            .await
+            .unwrap();
+
+        let latest = resolver.resolve_query(&query).await.unwrap();
+        assert_eq!(latest.content, vec![4]);
+
+        let options = ResolutionOptions {
+            version_selection: VersionSelection::HighestSemver,
+            ..Default::default()
+        };
+        let highest = resolver
+            .resolve_query_with_options(&query, &options)
+            .await
+            .unwrap();
+        assert_eq!(highest.content, vec![3]);
+        assert_eq!(highest.metadata.resource_version_id.unwrap(), "3.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_resource_names_and_types_are_distinct() {
+        let conf = TestConfig::load();
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+
+        let registrar = EthrDidLinkedResourcesRegistrar::new(signer, conf.get_dlr_network_config());
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+
+        // ("<name>ab", "c") and ("<name>a", "bc") concatenate the same way, but are different
+        // resources to a version 2 registry
+        let resource_name = format!("foo{}", uuid::Uuid::new_v4().simple());
+        let resource_input = ResourceInput::builder()
+            .resource_name(format!("{resource_name}ab"))
+            .resource_type("c")
+            .media_type("text/plain")
+            .content("hello")
+            .build()
+            .unwrap();
+        let ambiguous_input = ResourceInput {
+            resource_name: format!("{resource_name}a"),
+            resource_type: String::from("bc"),
+            content: b"world".to_vec(),
+            ..resource_input.clone()
+        };
+
+        let resources = registrar
+            .create_resources(&did, vec![resource_input, ambiguous_input])
+            .await
+            .unwrap();
+        assert!(resources
+            .iter()
+            .all(|resource| resource.metadata.previous_version_id.is_none()));
+
+        for resource in resources {
+            let query = format!(
+                "{did}?resourceName={}&resourceType={}",
+                resource.metadata.resource_name, resource.metadata.resource_type
+            );
+            let resolved = resolver.resolve_query(&query).await.unwrap();
+            assert_eq!(resolved.content, resource.content);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audit_controller_at_creation() {
+        let conf = TestConfig::load();
+
+        let original_controller = get_writer_ethers_client(8, &conf);
+        let new_controller = get_writer_ethers_client(9, &conf);
+        let did = did_identity_as_full_did(&original_controller.address(), conf.chain_id);
+
+        let did_registry = DidEthRegistry::new(conf.get_did_ethr_network_config());
+        let resolver = EthrDidLinkedResourcesResolver::new(conf.get_dlr_network_config());
+        let options = ResolutionOptions {
+            audit_controller: true,
+            ..Default::default()
+        };
+
+        let resource_input = ResourceInput {
+            resource_name: format!("foo{}", uuid::Uuid::new_v4()),
+            resource_type: String::from("bar"),
+            content: vec![1, 2, 3, 4],
+            ..Default::default()
+        };
+
+        let registrar = EthrDidLinkedResourcesRegistrar::new(
+            original_controller.clone(),
+            conf.get_dlr_network_config(),
+        );
+        let before_rotation = registrar
+            .create_resource(&did, resource_input.clone())
+            .await
+            .unwrap();
+
+        // rotate the controller key, and write with the new key
+        did_registry
+            .change_owner(original_controller.clone(), &did, new_controller.address())
+            .await;
+        let registrar = EthrDidLinkedResourcesRegistrar::new(
+            new_controller.clone(),
+            conf.get_dlr_network_config(),
+        );
+        let after_rotation = registrar
+            .create_resource(&did, resource_input)
+            .await
+            .unwrap();
+
+        // not audited by default
+        let resolved = resolver
+            .resolve_query(&before_rotation.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert!(resolved.metadata.controller_at_creation.is_none());
+
+        let resolved = resolver
+            .resolve_query_with_options(&before_rotation.metadata.resource_uri, &options)
+            .await
+            .unwrap();
+        let audit = resolved.metadata.controller_at_creation.unwrap();
+        assert_eq!(audit.controller, original_controller.address());
+        assert!(audit.sent_by_controller());
+
+        let resolved = resolver
+            .resolve_query_with_options(&after_rotation.metadata.resource_uri, &options)
+            .await
+            .unwrap();
+        let audit = resolved.metadata.controller_at_creation.unwrap();
+        assert_eq!(audit.controller, new_controller.address());
+        assert!(audit.sent_by_controller());
+
+        // restore the original controller for later runs
+        did_registry
+            .change_owner(new_controller, &did, original_controller.address())
+            .await;
     }
 }
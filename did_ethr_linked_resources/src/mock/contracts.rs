@@ -0,0 +1,718 @@
+//! Emulation of the `EthereumDIDRegistry` and `EthrDIDLinkedResourcesRegistry` (version 2)
+//! contracts, following their Solidity sources in `smart-contracts/contracts`.
+
+use std::collections::{HashMap, HashSet};
+
+use ethers::{
+    abi::{encode, AbiDecode, Token, Tokenizable},
+    contract::EthEvent,
+    types::{Address, Bytes, Signature, H256, U256},
+    utils::keccak256,
+};
+
+use crate::{
+    contracts::{
+        eth_did_registry::{
+            DidattributeChangedFilter, DiddelegateChangedFilter, DidownerChangedFilter,
+            EthereumDIDRegistryCalls,
+        },
+        ethr_dlr_registry::{
+            create_resource_signed_hash, EthrDLRRegistryCalls, LedgerTime, NewResourceFilter,
+            Resource, ResourceDeactivatedFilter, ResourceInputData, ResourceMetadata,
+            ResourceVersionMetadataChainNode, TOMBSTONE_MEDIA_TYPE,
+        },
+        registry_version::{resource_key, RegistryVersion},
+    },
+    types::input::ResourceInput,
+};
+
+/// Solidity panic code of arithmetic over/underflows
+const PANIC_ARITHMETIC: u64 = 0x11;
+/// Solidity panic code of out of bounds array accesses
+const PANIC_OUT_OF_BOUNDS: u64 = 0x32;
+
+/// The block which a call executes in, and its sender.
+pub(super) struct CallContext {
+    pub sender: Address,
+    pub block_number: u64,
+    pub timestamp: u64,
+}
+
+/// A log emitted by a call.
+pub(super) struct EmittedLog {
+    pub address: Address,
+    pub topics: Vec<H256>,
+    pub data: Bytes,
+}
+
+/// Why a call reverted.
+#[derive(Debug)]
+pub(super) enum Revert {
+    /// `require(.., reason)`
+    Reason(String),
+    /// a Solidity panic (e.g. [PANIC_OUT_OF_BOUNDS])
+    Panic(u64),
+}
+
+impl Revert {
+    fn reason(reason: &str) -> Self {
+        Revert::Reason(reason.to_owned())
+    }
+
+    /// the revert data, as returned by nodes (ABI encoded `Error(string)` or `Panic(uint256)`)
+    pub fn data(&self) -> Bytes {
+        let (signature, token) = match self {
+            Revert::Reason(reason) => ("Error(string)", Token::String(reason.clone())),
+            Revert::Panic(code) => ("Panic(uint256)", Token::Uint((*code).into())),
+        };
+        [&keccak256(signature)[..4], &encode(&[token])]
+            .concat()
+            .into()
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Revert::Reason(reason) => format!("execution reverted: {reason}"),
+            Revert::Panic(code) => format!("execution reverted: panic code {code:#x}"),
+        }
+    }
+}
+
+/// The state of both contracts.
+#[derive(Clone, Debug)]
+pub(super) struct Contracts {
+    pub did_registry_address: Address,
+    pub dlr_registry_address: Address,
+    did_registry: DidRegistry,
+    dlr_registry: DlrRegistry,
+}
+
+impl Contracts {
+    pub fn new(did_registry_address: Address, dlr_registry_address: Address) -> Self {
+        Self {
+            did_registry_address,
+            dlr_registry_address,
+            did_registry: DidRegistry::default(),
+            dlr_registry: DlrRegistry {
+                did_registry: did_registry_address,
+                global_resource_counter: U256::zero(),
+                resource_metadata_chains: HashMap::new(),
+                deactivated_resources: HashSet::new(),
+                nonce: HashMap::new(),
+            },
+        }
+    }
+
+    /// Call the contract at `to` with the ABI encoded `data`, returning the ABI encoded output
+    /// and the logs emitted. Like a transaction, the state is left unchanged if the call reverts.
+    /// Calls to other addresses (accounts without code) do nothing.
+    pub fn call(
+        &mut self,
+        context: &CallContext,
+        to: Address,
+        data: &[u8],
+    ) -> Result<(Bytes, Vec<EmittedLog>), Revert> {
+        let mut state = self.clone();
+        let mut logs = vec![];
+        let output = if to == self.did_registry_address {
+            state.did_registry.call(context, to, data, &mut logs)?
+        } else if to == self.dlr_registry_address {
+            let Contracts {
+                did_registry,
+                dlr_registry,
+                ..
+            } = &mut state;
+            dlr_registry.call(context, to, did_registry, data, &mut logs)?
+        } else {
+            Bytes::new()
+        };
+
+        *self = state;
+        Ok((output, logs))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct DidRegistry {
+    owners: HashMap<Address, Address>,
+    /// (identity, keccak256(abi.encode(delegateType)), delegate) -> valid until (timestamp)
+    delegates: HashMap<(Address, [u8; 32], Address), U256>,
+    changed: HashMap<Address, U256>,
+    nonce: HashMap<Address, U256>,
+}
+
+impl DidRegistry {
+    fn identity_owner(&self, identity: Address) -> Address {
+        match self.owners.get(&identity) {
+            Some(owner) if !owner.is_zero() => *owner,
+            _ => identity,
+        }
+    }
+
+    fn valid_delegate(
+        &self,
+        context: &CallContext,
+        identity: Address,
+        delegate_type: [u8; 32],
+        delegate: Address,
+    ) -> bool {
+        let key = (identity, delegate_type_key(delegate_type), delegate);
+        self.delegates
+            .get(&key)
+            .is_some_and(|validity| *validity > U256::from(context.timestamp))
+    }
+
+    fn only_owner(&self, identity: Address, actor: Address) -> Result<(), Revert> {
+        require(actor == self.identity_owner(identity), "bad_actor")
+    }
+
+    fn call(
+        &mut self,
+        context: &CallContext,
+        address: Address,
+        data: &[u8],
+        logs: &mut Vec<EmittedLog>,
+    ) -> Result<Bytes, Revert> {
+        let call = EthereumDIDRegistryCalls::decode(data).map_err(|_| unsupported())?;
+        let output = match call {
+            EthereumDIDRegistryCalls::IdentityOwner(call) => {
+                output(self.identity_owner(call.identity))
+            }
+            EthereumDIDRegistryCalls::Owners(call) => {
+                output(self.owners.get(&call.0).copied().unwrap_or_default())
+            }
+            EthereumDIDRegistryCalls::Delegates(call) => output(
+                self.delegates
+                    .get(&(call.0, call.1, call.2))
+                    .copied()
+                    .unwrap_or_default(),
+            ),
+            EthereumDIDRegistryCalls::ValidDelegate(call) => output(self.valid_delegate(
+                context,
+                call.identity,
+                call.delegate_type,
+                call.delegate,
+            )),
+            EthereumDIDRegistryCalls::Changed(call) => output(self.changed_at(call.0)),
+            EthereumDIDRegistryCalls::Nonce(call) => {
+                output(self.nonce.get(&call.0).copied().unwrap_or_default())
+            }
+            EthereumDIDRegistryCalls::ChangeOwner(call) => {
+                self.only_owner(call.identity, context.sender)?;
+                self.owners.insert(call.identity, call.new_owner);
+                logs.push(event::<DidownerChangedFilter>(
+                    address,
+                    call.identity,
+                    vec![
+                        call.new_owner.into_token(),
+                        self.changed_at(call.identity).into_token(),
+                    ],
+                ));
+                self.changed_now(context, call.identity);
+                Bytes::new()
+            }
+            EthereumDIDRegistryCalls::AddDelegate(call) => {
+                self.only_owner(call.identity, context.sender)?;
+                let valid_to = U256::from(context.timestamp)
+                    .checked_add(call.validity)
+                    .ok_or(Revert::Panic(PANIC_ARITHMETIC))?;
+                self.set_delegate(
+                    context,
+                    address,
+                    logs,
+                    call.identity,
+                    call.delegate_type,
+                    call.delegate,
+                    valid_to,
+                );
+                Bytes::new()
+            }
+            EthereumDIDRegistryCalls::RevokeDelegate(call) => {
+                self.only_owner(call.identity, context.sender)?;
+                let valid_to = U256::from(context.timestamp);
+                self.set_delegate(
+                    context,
+                    address,
+                    logs,
+                    call.identity,
+                    call.delegate_type,
+                    call.delegate,
+                    valid_to,
+                );
+                Bytes::new()
+            }
+            EthereumDIDRegistryCalls::SetAttribute(call) => {
+                self.only_owner(call.identity, context.sender)?;
+                let valid_to = U256::from(context.timestamp)
+                    .checked_add(call.validity)
+                    .ok_or(Revert::Panic(PANIC_ARITHMETIC))?;
+                logs.push(event::<DidattributeChangedFilter>(
+                    address,
+                    call.identity,
+                    vec![
+                        call.name.into_token(),
+                        call.value.into_token(),
+                        valid_to.into_token(),
+                        self.changed_at(call.identity).into_token(),
+                    ],
+                ));
+                self.changed_now(context, call.identity);
+                Bytes::new()
+            }
+            EthereumDIDRegistryCalls::RevokeAttribute(call) => {
+                self.only_owner(call.identity, context.sender)?;
+                logs.push(event::<DidattributeChangedFilter>(
+                    address,
+                    call.identity,
+                    vec![
+                        call.name.into_token(),
+                        call.value.into_token(),
+                        U256::zero().into_token(),
+                        self.changed_at(call.identity).into_token(),
+                    ],
+                ));
+                self.changed_now(context, call.identity);
+                Bytes::new()
+            }
+            // the signed (meta transaction) variants are not emulated
+            _ => return Err(unsupported()),
+        };
+
+        Ok(output)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn set_delegate(
+        &mut self,
+        context: &CallContext,
+        address: Address,
+        logs: &mut Vec<EmittedLog>,
+        identity: Address,
+        delegate_type: [u8; 32],
+        delegate: Address,
+        valid_to: U256,
+    ) {
+        let key = (identity, delegate_type_key(delegate_type), delegate);
+        self.delegates.insert(key, valid_to);
+        logs.push(event::<DiddelegateChangedFilter>(
+            address,
+            identity,
+            vec![
+                delegate_type.into_token(),
+                delegate.into_token(),
+                valid_to.into_token(),
+                self.changed_at(identity).into_token(),
+            ],
+        ));
+        self.changed_now(context, identity);
+    }
+
+    fn changed_at(&self, identity: Address) -> U256 {
+        self.changed.get(&identity).copied().unwrap_or_default()
+    }
+
+    fn changed_now(&mut self, context: &CallContext, identity: Address) {
+        self.changed
+            .insert(identity, U256::from(context.block_number));
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DlrRegistry {
+    /// address of the [DidRegistry]
+    did_registry: Address,
+    global_resource_counter: U256,
+    /// (didIdentity, resourceKey) -> metadata chain
+    resource_metadata_chains: HashMap<(Address, [u8; 32]), Vec<ResourceVersionMetadataChainNode>>,
+    /// (didIdentity, resourceKey) of resources whose latest version is a tombstone
+    deactivated_resources: HashSet<(Address, [u8; 32])>,
+    nonce: HashMap<Address, U256>,
+}
+
+impl DlrRegistry {
+    fn call(
+        &mut self,
+        context: &CallContext,
+        address: Address,
+        did_registry: &DidRegistry,
+        data: &[u8],
+        logs: &mut Vec<EmittedLog>,
+    ) -> Result<Bytes, Revert> {
+        let only_owner = |identity: Address| {
+            require(
+                context.sender == did_registry.identity_owner(identity),
+                "bad_actor",
+            )
+        };
+        let only_owner_or_delegate = |identity: Address, delegate_type: [u8; 32]| {
+            require(
+                context.sender == did_registry.identity_owner(identity)
+                    || did_registry.valid_delegate(
+                        context,
+                        identity,
+                        delegate_type,
+                        context.sender,
+                    ),
+                "bad_actor",
+            )
+        };
+
+        let call = EthrDLRRegistryCalls::decode(data).map_err(|_| unsupported())?;
+        let output = match call {
+            EthrDLRRegistryCalls::RegistryVersion(_) => output(U256::from(2)),
+            EthrDLRRegistryCalls::TombstoneMediaType(_) => output(TOMBSTONE_MEDIA_TYPE.to_owned()),
+            EthrDLRRegistryCalls::DidRegistry(_) => output(self.did_registry),
+            EthrDLRRegistryCalls::Nonce(call) => {
+                output(self.nonce.get(&call.0).copied().unwrap_or_default())
+            }
+            EthrDLRRegistryCalls::GetResourceKey(call) => {
+                output(resource_key(&call.resource_name, &call.resource_type))
+            }
+            EthrDLRRegistryCalls::IsResourceDeactivated(call) => output(
+                self.deactivated_resources
+                    .contains(&(call.did_identity, call.resource_key)),
+            ),
+            EthrDLRRegistryCalls::GetResourceMetadataChain(call) => {
+                output(self.chain(call.did_identity, call.resource_key).to_vec())
+            }
+            EthrDLRRegistryCalls::GetResourceMetadataChainLength(call) => output(U256::from(
+                self.chain(call.did_identity, call.resource_key).len(),
+            )),
+            EthrDLRRegistryCalls::GetResourceMetadataChainNode(call) => {
+                let chain = self.chain(call.did_identity, call.resource_key);
+                output(node_at(chain, call.index)?)
+            }
+            EthrDLRRegistryCalls::GetResourceMetadataChainLatestNode(call) => {
+                let chain = self.chain(call.did_identity, call.resource_key);
+                let latest = chain.last().ok_or(Revert::Panic(PANIC_ARITHMETIC))?;
+                output(latest.clone())
+            }
+            EthrDLRRegistryCalls::GetResourceMetadataChainSlice(call) => {
+                let chain = self.chain(call.did_identity, call.resource_key);
+                if call.end < call.start {
+                    return Err(Revert::Panic(PANIC_ARITHMETIC));
+                }
+                let mut slice = vec![];
+                let mut index = call.start;
+                while index < call.end {
+                    slice.push(node_at(chain, index)?);
+                    index += U256::one();
+                }
+                output(slice)
+            }
+            EthrDLRRegistryCalls::CreateResourceSignedHash(call) => {
+                let owner = did_registry.identity_owner(call.did_identity);
+                output(
+                    create_resource_signed_hash(
+                        address,
+                        self.nonce.get(&owner).copied().unwrap_or_default(),
+                        call.did_identity,
+                        &ResourceInput {
+                            resource_name: call.resource_name,
+                            resource_type: call.resource_type,
+                            resource_version_id: call.resource_version,
+                            media_type: call.media_type,
+                            content: call.content.to_vec(),
+                            ..Default::default()
+                        },
+                    )
+                    .0,
+                )
+            }
+            EthrDLRRegistryCalls::CreateResource(call) => {
+                only_owner(call.did_identity)?;
+                self.write_resource(
+                    context,
+                    address,
+                    logs,
+                    call.did_identity,
+                    ResourceInputData {
+                        resource_name: call.resource_name,
+                        resource_type: call.resource_type,
+                        resource_version: call.resource_version,
+                        media_type: call.media_type,
+                        content: call.content,
+                    },
+                )?;
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::CreateResourceAsDelegate(call) => {
+                only_owner_or_delegate(call.did_identity, call.delegate_type)?;
+                self.write_resource(
+                    context,
+                    address,
+                    logs,
+                    call.did_identity,
+                    ResourceInputData {
+                        resource_name: call.resource_name,
+                        resource_type: call.resource_type,
+                        resource_version: call.resource_version,
+                        media_type: call.media_type,
+                        content: call.content,
+                    },
+                )?;
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::CreateResources(call) => {
+                only_owner(call.did_identity)?;
+                for resource in call.resources {
+                    self.write_resource(context, address, logs, call.did_identity, resource)?;
+                }
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::CreateResourcesAsDelegate(call) => {
+                only_owner_or_delegate(call.did_identity, call.delegate_type)?;
+                for resource in call.resources {
+                    self.write_resource(context, address, logs, call.did_identity, resource)?;
+                }
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::CreateResourceSigned(call) => {
+                let owner = did_registry.identity_owner(call.did_identity);
+                let resource = ResourceInputData {
+                    resource_name: call.resource_name,
+                    resource_type: call.resource_type,
+                    resource_version: call.resource_version,
+                    media_type: call.media_type,
+                    content: call.content,
+                };
+                let hash = create_resource_signed_hash(
+                    address,
+                    self.nonce.get(&owner).copied().unwrap_or_default(),
+                    call.did_identity,
+                    &ResourceInput {
+                        resource_name: resource.resource_name.clone(),
+                        resource_type: resource.resource_type.clone(),
+                        resource_version_id: resource.resource_version.clone(),
+                        media_type: resource.media_type.clone(),
+                        content: resource.content.to_vec(),
+                        ..Default::default()
+                    },
+                );
+                let signature = Signature {
+                    r: U256::from_big_endian(&call.sig_r),
+                    s: U256::from_big_endian(&call.sig_s),
+                    v: call.sig_v.into(),
+                };
+                // like `ecrecover`, an invalid signature recovers no signer
+                let signer = signature.recover(hash).unwrap_or_default();
+                require(signer == owner, "bad_signature")?;
+                *self.nonce.entry(signer).or_default() += U256::one();
+
+                self.write_resource(context, address, logs, call.did_identity, resource)?;
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::DeactivateResource(call) => {
+                only_owner(call.did_identity)?;
+                self.write_tombstone(
+                    context,
+                    address,
+                    logs,
+                    call.did_identity,
+                    call.resource_name,
+                    call.resource_type,
+                )?;
+                Bytes::new()
+            }
+            EthrDLRRegistryCalls::DeactivateResourceAsDelegate(call) => {
+                only_owner_or_delegate(call.did_identity, call.delegate_type)?;
+                self.write_tombstone(
+                    context,
+                    address,
+                    logs,
+                    call.did_identity,
+                    call.resource_name,
+                    call.resource_type,
+                )?;
+                Bytes::new()
+            }
+        };
+
+        Ok(output)
+    }
+
+    fn chain(
+        &self,
+        did_identity: Address,
+        resource_key: [u8; 32],
+    ) -> &[ResourceVersionMetadataChainNode] {
+        self.resource_metadata_chains
+            .get(&(did_identity, resource_key))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    fn write_tombstone(
+        &mut self,
+        context: &CallContext,
+        address: Address,
+        logs: &mut Vec<EmittedLog>,
+        did_identity: Address,
+        resource_name: String,
+        resource_type: String,
+    ) -> Result<(), Revert> {
+        let key = resource_key(&resource_name, &resource_type);
+        require(!self.chain(did_identity, key).is_empty(), "not_found")?;
+        require(
+            !self.deactivated_resources.contains(&(did_identity, key)),
+            "already_deactivated",
+        )?;
+
+        let preimage = RegistryVersion::V2.resource_key_preimage(&resource_name, &resource_type);
+        let resource_id = self.write_resource(
+            context,
+            address,
+            logs,
+            did_identity,
+            ResourceInputData {
+                resource_name,
+                resource_type,
+                resource_version: String::new(),
+                media_type: TOMBSTONE_MEDIA_TYPE.to_owned(),
+                content: Bytes::new(),
+            },
+        )?;
+        self.deactivated_resources.insert((did_identity, key));
+
+        logs.push(EmittedLog {
+            address,
+            topics: vec![
+                ResourceDeactivatedFilter::signature(),
+                did_identity.into(),
+                u256_topic(resource_id),
+                H256(keccak256(preimage)),
+            ],
+            data: Bytes::new(),
+        });
+        Ok(())
+    }
+
+    fn write_resource(
+        &mut self,
+        context: &CallContext,
+        address: Address,
+        logs: &mut Vec<EmittedLog>,
+        did_identity: Address,
+        input: ResourceInputData,
+    ) -> Result<U256, Revert> {
+        self.global_resource_counter += U256::one();
+
+        let resource_id = self.global_resource_counter;
+        let key = resource_key(&input.resource_name, &input.resource_type);
+
+        self.deactivated_resources.remove(&(did_identity, key));
+
+        let metadata_chain_node_index =
+            self.update_metadata_chain(context, did_identity, key, resource_id);
+
+        let preimage =
+            RegistryVersion::V2.resource_key_preimage(&input.resource_name, &input.resource_type);
+        let resource = Resource {
+            resource_id,
+            metadata: ResourceMetadata {
+                resource_name: input.resource_name,
+                resource_type: input.resource_type,
+                resource_version: input.resource_version,
+                media_type: input.media_type,
+                created: ledger_time(context),
+                metadata_chain_node_index,
+            },
+            content: input.content,
+        };
+        logs.push(EmittedLog {
+            address,
+            topics: vec![
+                NewResourceFilter::signature(),
+                did_identity.into(),
+                u256_topic(resource_id),
+                H256(keccak256(preimage)),
+            ],
+            data: encode(&[resource.into_token()]).into(),
+        });
+
+        Ok(resource_id)
+    }
+
+    fn update_metadata_chain(
+        &mut self,
+        context: &CallContext,
+        did_identity: Address,
+        resource_key: [u8; 32],
+        resource_id: U256,
+    ) -> U256 {
+        let chain = self
+            .resource_metadata_chains
+            .entry((did_identity, resource_key))
+            .or_default();
+        let chain_length = chain.len();
+
+        let mut previous_resource_id = U256::zero();
+        if let Some(previous) = chain.last_mut() {
+            previous.next_resource_id = resource_id;
+            previous_resource_id = previous.resource_id;
+        }
+
+        chain.push(ResourceVersionMetadataChainNode {
+            resource_id,
+            created: ledger_time(context),
+            next_resource_id: U256::zero(),
+            previous_resource_id,
+        });
+
+        // index is length pre push
+        U256::from(chain_length)
+    }
+}
+
+fn node_at(
+    chain: &[ResourceVersionMetadataChainNode],
+    index: U256,
+) -> Result<ResourceVersionMetadataChainNode, Revert> {
+    if index >= U256::from(chain.len()) {
+        return Err(Revert::Panic(PANIC_OUT_OF_BOUNDS));
+    }
+    Ok(chain[index.as_usize()].clone())
+}
+
+fn ledger_time(context: &CallContext) -> LedgerTime {
+    LedgerTime {
+        block_timestamp: context.timestamp,
+        block_number: context.block_number,
+    }
+}
+
+/// the key of a delegate type in the `delegates` of the `EthereumDIDRegistry`
+fn delegate_type_key(delegate_type: [u8; 32]) -> [u8; 32] {
+    keccak256(encode(&[delegate_type.into_token()]))
+}
+
+/// a `DIDOwnerChanged`-like event of the `EthereumDIDRegistry`, whose only indexed parameter is
+/// the `identity`
+fn event<E: EthEvent>(address: Address, identity: Address, data: Vec<Token>) -> EmittedLog {
+    EmittedLog {
+        address,
+        topics: vec![E::signature(), identity.into()],
+        data: encode(&data).into(),
+    }
+}
+
+fn output<T: Tokenizable>(value: T) -> Bytes {
+    encode(&[value.into_token()]).into()
+}
+
+fn u256_topic(value: U256) -> H256 {
+    let mut topic = H256::zero();
+    value.to_big_endian(topic.as_bytes_mut());
+    topic
+}
+
+fn require(condition: bool, reason: &str) -> Result<(), Revert> {
+    if condition {
+        Ok(())
+    } else {
+        Err(Revert::reason(reason))
+    }
+}
+
+fn unsupported() -> Revert {
+    Revert::reason("mock: unsupported function")
+}
@@ -0,0 +1,1161 @@
+//! An in-memory ledger for unit tests (with the `mock` feature), so that resources can be
+//! written and resolved in plain `cargo test`, without a node or deployed contracts.
+//!
+//! [MockLedger] is a JSON-RPC client which emulates the `EthereumDIDRegistry` and the (version 2)
+//! `EthrDIDLinkedResourcesRegistry` with the same semantics as the contracts: global counter
+//! resource ids, metadata chains, DID owner & delegate checks and reverts. Every transaction is
+//! mined immediately into its own block, timestamped by the ledger's [Clock]. With automining
+//! off (see [MockLedger::set_automine]), sent transactions are pending until
+//! [MockLedger::mine_pending], and can be replaced (with higher fees) or dropped
+//! ([MockLedger::drop_transaction]) meanwhile.
+//!
+//! ```ignore
+//! let clock = Arc::new(ManualClock::new(1_700_000_000));
+//! let ledger = MockLedger::with_clock(clock.clone());
+//!
+//! let signer = ledger.random_signer();
+//! let did = did_identity_as_full_did(&signer.address(), ledger.chain_id());
+//! let registrar = ledger.registrar(signer);
+//! let resolver = ledger.resolver();
+//!
+//! registrar.create_resource(&did, resource_input).await?;
+//! clock.advance(60);
+//! ```
+//!
+//! Accounts have unlimited funds, and only calls of the two contracts are supported. Resolution
+//! is via RPC, so the `thegraph` feature should not be enabled by tests using the ledger.
+
+mod contracts;
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use ethers::{
+    core::rand::thread_rng,
+    middleware::SignerMiddleware,
+    providers::{JsonRpcClient, JsonRpcError, Middleware, MockError, Provider},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Block, BlockNumber, Bytes, FeeHistory,
+        Filter, FilterBlockOption, Log, NameOrAddress, Signature, Transaction, TransactionReceipt,
+        ValueOrArray, H160, H256, U256, U64,
+    },
+    utils::{keccak256, rlp::Rlp},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    config::ContractNetworkConfig, registrar::EthrDidLinkedResourcesRegistrar,
+    resolver::EthrDidLinkedResourcesResolver,
+};
+
+use self::contracts::{CallContext, Contracts, EmittedLog, Revert};
+
+/// Chain ID of the mock ledger (that of local Hardhat nodes, so DIDs are `did:ethr:local:..`).
+pub const MOCK_CHAIN_ID: u64 = 31337;
+
+const DID_REGISTRY_ADDRESS: Address = H160([0xd1; 20]);
+const DLR_REGISTRY_ADDRESS: Address = H160([0xd2; 20]);
+
+const BASE_FEE_PER_GAS: u64 = 1_000_000_000;
+const PRIORITY_FEE_PER_GAS: u64 = 1_000_000_000;
+const BLOCK_GAS_LIMIT: u64 = 30_000_000;
+
+/// A signer writing to a [MockLedger], see [MockLedger::signer].
+pub type MockSigner = SignerMiddleware<Provider<MockLedger>, LocalWallet>;
+
+/// The source of the timestamps of blocks mined by a [MockLedger].
+pub trait Clock: Debug + Send + Sync {
+    /// the current time, in seconds since the unix epoch
+    fn now(&self) -> u64;
+}
+
+/// The system time.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// A clock which only moves when set, for deterministic timestamps.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: AtomicU64::new(now),
+        }
+    }
+
+    pub fn set(&self, now: u64) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.now.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.load(Ordering::SeqCst)
+    }
+}
+
+/// An in-memory ledger, with the registries deployed (see the [module docs](self)). Clones
+/// share the same ledger.
+#[derive(Clone, Debug)]
+pub struct MockLedger {
+    ledger: Arc<Mutex<Ledger>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for MockLedger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockLedger {
+    /// A ledger whose blocks are timestamped with the system time.
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// A ledger whose blocks are timestamped by the `clock`. Block timestamps never go back
+    /// before that of the previous block, even if the clock does.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        let ledger = Ledger::new(clock.now());
+        Self {
+            ledger: Arc::new(Mutex::new(ledger)),
+            clock,
+        }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        MOCK_CHAIN_ID
+    }
+
+    /// The config of the `EthrDIDLinkedResourcesRegistry` (the `rpc_url` is not used, see
+    /// [Self::resolver] & [Self::registrar]).
+    pub fn dlr_network_config(&self) -> ContractNetworkConfig {
+        self.network_config(DLR_REGISTRY_ADDRESS)
+    }
+
+    /// The config of the `EthereumDIDRegistry`.
+    pub fn did_ethr_network_config(&self) -> ContractNetworkConfig {
+        self.network_config(DID_REGISTRY_ADDRESS)
+    }
+
+    fn network_config(&self, contract_address: Address) -> ContractNetworkConfig {
+        ContractNetworkConfig {
+            rpc_url: String::from("mock://ledger"),
+            contract_address: format!("{contract_address:?}"),
+            chain_id: self.chain_id(),
+        }
+    }
+
+    /// Whether sent transactions are mined immediately (the default). If not, they are pending
+    /// until [Self::mine_pending].
+    pub fn set_automine(&self, automine: bool) {
+        self.ledger.lock().unwrap().automine = automine;
+    }
+
+    /// Mine the pending transactions, each into its own block, in nonce order. As on nodes,
+    /// transactions after a gap in their sender's nonces stay pending until the gap is filled.
+    pub fn mine_pending(&self) {
+        self.ledger.lock().unwrap().mine_pending(self.clock.now());
+    }
+
+    /// Drop the pending transaction `hash` (as nodes do, e.g. when their mempool is full).
+    /// Returns whether it was pending.
+    pub fn drop_transaction(&self, hash: H256) -> bool {
+        let mut ledger = self.ledger.lock().unwrap();
+        let pending_count = ledger.pending.len();
+        ledger.pending.retain(|pending| pending.hash != hash);
+        ledger.pending.len() < pending_count
+    }
+
+    /// A provider reading from (and sending unsigned transactions to) this ledger.
+    pub fn provider(&self) -> Provider<MockLedger> {
+        // transactions are mined immediately, so there is no need to wait between polls
+        Provider::new(self.clone()).interval(Duration::from_millis(10))
+    }
+
+    /// A signer of transactions from the `wallet`, sent to this ledger.
+    pub fn signer(&self, wallet: LocalWallet) -> Arc<MockSigner> {
+        let wallet = wallet.with_chain_id(self.chain_id());
+        Arc::new(SignerMiddleware::new(self.provider(), wallet))
+    }
+
+    /// A signer with a new random wallet.
+    pub fn random_signer(&self) -> Arc<MockSigner> {
+        self.signer(LocalWallet::new(&mut thread_rng()))
+    }
+
+    /// A resolver of resources written to this ledger.
+    pub fn resolver(&self) -> EthrDidLinkedResourcesResolver<Provider<MockLedger>> {
+        EthrDidLinkedResourcesResolver::with_client(
+            self.dlr_network_config(),
+            Arc::new(self.provider()),
+        )
+    }
+
+    /// A registrar of resources written to this ledger by the `signer` (e.g. a [MockSigner]).
+    pub fn registrar<S: Middleware>(
+        &self,
+        signer: Arc<S>,
+    ) -> EthrDidLinkedResourcesRegistrar<S, Provider<MockLedger>> {
+        EthrDidLinkedResourcesRegistrar::with_client(
+            signer,
+            self.dlr_network_config(),
+            Arc::new(self.provider()),
+        )
+    }
+
+    fn handle(&self, method: &str, params: Value) -> Result<Value, MockError> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let now = self.clock.now();
+
+        let result = match method {
+            "eth_chainId" => to_json(U64::from(self.chain_id()))?,
+            "net_version" => to_json(self.chain_id().to_string())?,
+            "eth_blockNumber" => to_json(ledger.latest_block().number)?,
+            "eth_getBlockByNumber" => {
+                let number: BlockNumber = param(&params, 0)?;
+                let full: bool = param(&params, 1)?;
+                let block = ledger.block(number);
+                let mut block_json = to_json(block)?;
+                if let (Some(block), true) = (block, full) {
+                    let transactions: Vec<&Transaction> = block
+                        .transactions
+                        .iter()
+                        .map(|hash| &ledger.transactions[hash].0)
+                        .collect();
+                    block_json["transactions"] = to_json(transactions)?;
+                }
+                block_json
+            }
+            "eth_feeHistory" => {
+                let block_count: U256 = param(&params, 0)?;
+                let block_count = block_count.min(ledger.blocks.len().into()).as_usize();
+                let newest = ledger.latest_block().number.unwrap_or_default().as_u64();
+                to_json(FeeHistory {
+                    base_fee_per_gas: vec![BASE_FEE_PER_GAS.into(); block_count + 1],
+                    gas_used_ratio: vec![0.5; block_count],
+                    oldest_block: (newest + 1 - block_count as u64).into(),
+                    reward: vec![vec![PRIORITY_FEE_PER_GAS.into()]; block_count],
+                })?
+            }
+            "eth_gasPrice" => to_json(U256::from(BASE_FEE_PER_GAS + PRIORITY_FEE_PER_GAS))?,
+            "eth_maxPriorityFeePerGas" => to_json(U256::from(PRIORITY_FEE_PER_GAS))?,
+            "eth_getBalance" => to_json(U256::MAX)?,
+            "eth_getTransactionCount" => {
+                let address: Address = param(&params, 0)?;
+                let block: Option<BlockNumber> = param(&params, 1)?;
+                match block {
+                    Some(BlockNumber::Pending) => to_json(ledger.pending_nonce(address))?,
+                    _ => to_json(ledger.nonce(address))?,
+                }
+            }
+            "eth_call" => {
+                let request: CallRequest = param(&params, 0)?;
+                let (output, _) = ledger.dry_run(now, &request)?;
+                to_json(output)?
+            }
+            "eth_estimateGas" => {
+                let request: CallRequest = param(&params, 0)?;
+                let (_, logs) = ledger.dry_run(now, &request)?;
+                to_json(gas_used(&request.data(), &logs))?
+            }
+            "eth_sendRawTransaction" => {
+                let raw: Bytes = param(&params, 0)?;
+                let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+                    .map_err(|e| invalid_params(e.to_string()))?;
+                let from = signature
+                    .recover(tx.sighash())
+                    .map_err(|e| invalid_params(e.to_string()))?;
+                if tx
+                    .chain_id()
+                    .is_some_and(|id| id.as_u64() != self.chain_id())
+                {
+                    return Err(json_rpc_error(-32000, "invalid chain id", None));
+                }
+                to_json(ledger.send(now, from, tx.clone(), tx.hash(&signature), signature)?)?
+            }
+            "eth_sendTransaction" => {
+                let mut tx: TypedTransaction = param(&params, 0)?;
+                let from = *tx
+                    .from()
+                    .ok_or_else(|| invalid_params("missing from address"))?;
+                if tx.nonce().is_none() {
+                    tx.set_nonce(ledger.pending_nonce(from));
+                }
+                // unsigned, so hashed with the sender
+                let hash = H256(keccak256(
+                    [tx.sighash().as_bytes(), from.as_bytes()].concat(),
+                ));
+                let signature = Signature {
+                    r: U256::zero(),
+                    s: U256::zero(),
+                    v: 0,
+                };
+                to_json(ledger.send(now, from, tx, hash, signature)?)?
+            }
+            "eth_getTransactionByHash" => {
+                let hash: H256 = param(&params, 0)?;
+                match ledger.transactions.get(&hash) {
+                    Some((tx, _)) => to_json(tx)?,
+                    None => to_json(ledger.pending_transaction(hash))?,
+                }
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: H256 = param(&params, 0)?;
+                to_json(ledger.transactions.get(&hash).map(|(_, receipt)| receipt))?
+            }
+            "eth_getLogs" => {
+                let filter: Filter = param(&params, 0)?;
+                let logs: Vec<&Log> = ledger
+                    .logs
+                    .iter()
+                    .filter(|log| ledger.matches(&filter, log))
+                    .collect();
+                to_json(logs)?
+            }
+            _ => {
+                return Err(json_rpc_error(
+                    -32601,
+                    &format!("mock: the method {method} is not supported"),
+                    None,
+                ))
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MockLedger {
+    type Error = MockError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        let result = self.handle(method, params)?;
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/// The fields of an `eth_call` (or `eth_estimateGas`) request which are used.
+#[derive(Debug, Deserialize)]
+struct CallRequest {
+    from: Option<Address>,
+    to: Option<Address>,
+    data: Option<Bytes>,
+    input: Option<Bytes>,
+}
+
+impl CallRequest {
+    fn data(&self) -> Bytes {
+        self.input.clone().or(self.data.clone()).unwrap_or_default()
+    }
+}
+
+/// A sent transaction which is not mined yet.
+#[derive(Debug)]
+struct PendingTx {
+    from: Address,
+    tx: TypedTransaction,
+    hash: H256,
+    signature: Signature,
+}
+
+impl PendingTx {
+    fn nonce(&self) -> U256 {
+        self.tx.nonce().copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug)]
+struct Ledger {
+    contracts: Contracts,
+    blocks: Vec<Block<H256>>,
+    transactions: HashMap<H256, (Transaction, TransactionReceipt)>,
+    logs: Vec<Log>,
+    nonces: HashMap<Address, U256>,
+    pending: Vec<PendingTx>,
+    automine: bool,
+}
+
+impl Ledger {
+    fn new(genesis_timestamp: u64) -> Self {
+        let genesis = Block {
+            hash: Some(block_hash(0)),
+            number: Some(U64::zero()),
+            timestamp: genesis_timestamp.into(),
+            gas_limit: BLOCK_GAS_LIMIT.into(),
+            base_fee_per_gas: Some(BASE_FEE_PER_GAS.into()),
+            ..Default::default()
+        };
+
+        Self {
+            contracts: Contracts::new(DID_REGISTRY_ADDRESS, DLR_REGISTRY_ADDRESS),
+            blocks: vec![genesis],
+            transactions: HashMap::new(),
+            logs: vec![],
+            nonces: HashMap::new(),
+            pending: vec![],
+            automine: true,
+        }
+    }
+
+    fn latest_block(&self) -> &Block<H256> {
+        // there is always the genesis block
+        self.blocks.last().unwrap()
+    }
+
+    fn block(&self, number: BlockNumber) -> Option<&Block<H256>> {
+        let number = self.block_number(number);
+        self.blocks.get(usize::try_from(number).ok()?)
+    }
+
+    fn block_number(&self, number: BlockNumber) -> u64 {
+        match number {
+            BlockNumber::Earliest => 0,
+            BlockNumber::Number(number) => number.as_u64(),
+            _ => (self.blocks.len() - 1) as u64,
+        }
+    }
+
+    fn nonce(&self, address: Address) -> U256 {
+        self.nonces.get(&address).copied().unwrap_or_default()
+    }
+
+    /// the nonce after the sender's pending transactions (up to the first gap)
+    fn pending_nonce(&self, address: Address) -> U256 {
+        let mut nonce = self.nonce(address);
+        while self.pending_index(address, nonce).is_some() {
+            nonce += U256::one();
+        }
+        nonce
+    }
+
+    fn pending_index(&self, from: Address, nonce: U256) -> Option<usize> {
+        self.pending
+            .iter()
+            .position(|pending| pending.from == from && pending.nonce() == nonce)
+    }
+
+    fn pending_transaction(&self, hash: H256) -> Option<Transaction> {
+        let pending = self.pending.iter().find(|pending| pending.hash == hash)?;
+        Some(transaction(
+            pending.from,
+            &pending.tx,
+            pending.hash,
+            pending.signature,
+            None,
+        ))
+    }
+
+    /// add the transaction to the pending transactions (replacing a pending transaction of the
+    /// same nonce, if its fees are at least 10% higher), mining it if automining
+    fn send(
+        &mut self,
+        now: u64,
+        from: Address,
+        tx: TypedTransaction,
+        hash: H256,
+        signature: Signature,
+    ) -> Result<H256, MockError> {
+        let nonce = tx.nonce().copied().unwrap_or(self.pending_nonce(from));
+        if nonce < self.nonce(from) {
+            return Err(json_rpc_error(-32000, "nonce too low", None));
+        }
+        if !matches!(tx.to(), Some(NameOrAddress::Address(_))) {
+            return Err(invalid_params(
+                "contract creation and ENS names are not supported",
+            ));
+        }
+
+        if let Some(index) = self.pending_index(from, nonce) {
+            let replaced_fee = self.pending[index].tx.gas_price().unwrap_or_default();
+            let fee = tx.gas_price().unwrap_or_default();
+            if fee * 100 < replaced_fee * 110 {
+                return Err(json_rpc_error(
+                    -32000,
+                    "replacement transaction underpriced",
+                    None,
+                ));
+            }
+            self.pending.remove(index);
+        }
+
+        let mut tx = tx;
+        tx.set_nonce(nonce);
+        self.pending.push(PendingTx {
+            from,
+            tx,
+            hash,
+            signature,
+        });
+        if self.automine {
+            self.mine_pending(now);
+        }
+
+        Ok(hash)
+    }
+
+    /// mine the pending transactions which are next in their sender's nonces, until none are
+    fn mine_pending(&mut self, now: u64) {
+        while let Some(index) = self
+            .pending
+            .iter()
+            .position(|pending| pending.nonce() == self.nonce(pending.from))
+        {
+            let pending = self.pending.remove(index);
+            self.mine(now, pending);
+        }
+    }
+
+    /// the context of a call made now, in the next block
+    fn next_block_context(&self, now: u64, sender: Address) -> CallContext {
+        let latest = self.latest_block();
+        CallContext {
+            sender,
+            block_number: latest.number.unwrap_or_default().as_u64() + 1,
+            timestamp: now.max(latest.timestamp.as_u64()),
+        }
+    }
+
+    /// execute the call without changing the state
+    fn dry_run(
+        &self,
+        now: u64,
+        request: &CallRequest,
+    ) -> Result<(Bytes, Vec<EmittedLog>), MockError> {
+        let to = request
+            .to
+            .ok_or_else(|| invalid_params("contract creation is not supported"))?;
+        let context = self.next_block_context(now, request.from.unwrap_or_default());
+        self.contracts
+            .clone()
+            .call(&context, to, &request.data())
+            .map_err(revert_error)
+    }
+
+    /// mine the (next in nonce) pending transaction into a new block
+    fn mine(&mut self, now: u64, pending: PendingTx) {
+        let PendingTx {
+            from,
+            tx,
+            hash,
+            signature,
+        } = pending;
+        let nonce = tx.nonce().copied().unwrap_or_default();
+        // checked when sent
+        let Some(NameOrAddress::Address(to)) = tx.to().cloned() else {
+            return;
+        };
+
+        let context = self.next_block_context(now, from);
+        let data = tx.data().cloned().unwrap_or_default();
+        // reverted transactions are still mined (consuming the nonce), but change nothing
+        let (status, emitted_logs) = match self.contracts.call(&context, to, &data) {
+            Ok((_, logs)) => (1, logs),
+            Err(_) => (0, vec![]),
+        };
+        let gas_used = gas_used(&data, &emitted_logs);
+
+        let block_number = U64::from(context.block_number);
+        let block_hash = block_hash(context.block_number);
+        let logs: Vec<Log> = emitted_logs
+            .into_iter()
+            .enumerate()
+            .map(|(index, log)| Log {
+                address: log.address,
+                topics: log.topics,
+                data: log.data,
+                block_hash: Some(block_hash),
+                block_number: Some(block_number),
+                transaction_hash: Some(hash),
+                transaction_index: Some(U64::zero()),
+                log_index: Some(index.into()),
+                transaction_log_index: Some(index.into()),
+                removed: Some(false),
+                ..Default::default()
+            })
+            .collect();
+
+        let mut transaction =
+            transaction(from, &tx, hash, signature, Some((block_hash, block_number)));
+        if tx.gas().is_none() {
+            transaction.gas = gas_used;
+        }
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            transaction_index: U64::zero(),
+            block_hash: Some(block_hash),
+            block_number: Some(block_number),
+            from,
+            to: Some(to),
+            cumulative_gas_used: gas_used,
+            gas_used: Some(gas_used),
+            logs: logs.clone(),
+            status: Some(status.into()),
+            transaction_type: transaction.transaction_type,
+            effective_gas_price: transaction.gas_price,
+            ..Default::default()
+        };
+        let block = Block {
+            hash: Some(block_hash),
+            parent_hash: self.latest_block().hash.unwrap_or_default(),
+            number: Some(block_number),
+            timestamp: context.timestamp.into(),
+            gas_limit: BLOCK_GAS_LIMIT.into(),
+            gas_used,
+            base_fee_per_gas: Some(BASE_FEE_PER_GAS.into()),
+            transactions: vec![hash],
+            ..Default::default()
+        };
+
+        self.nonces.insert(from, nonce + 1);
+        self.logs.extend(logs);
+        self.transactions.insert(hash, (transaction, receipt));
+        self.blocks.push(block);
+    }
+
+    fn matches(&self, filter: &Filter, log: &Log) -> bool {
+        let address_matches = match &filter.address {
+            None => true,
+            Some(ValueOrArray::Value(address)) => log.address == *address,
+            Some(ValueOrArray::Array(addresses)) => {
+                addresses.is_empty() || addresses.contains(&log.address)
+            }
+        };
+
+        let block_matches = match filter.block_option {
+            FilterBlockOption::AtBlockHash(hash) => log.block_hash == Some(hash),
+            FilterBlockOption::Range {
+                from_block,
+                to_block,
+            } => {
+                let number = log.block_number.unwrap_or_default().as_u64();
+                let from_block = from_block.map_or(0, |from| self.block_number(from));
+                let to_block = self.block_number(to_block.unwrap_or_default());
+                (from_block..=to_block).contains(&number)
+            }
+        };
+
+        let topics_match = filter.topics.iter().enumerate().all(|(i, topic)| {
+            let log_topic = log.topics.get(i);
+            match topic {
+                None | Some(ValueOrArray::Value(None)) => true,
+                Some(ValueOrArray::Value(Some(topic))) => log_topic == Some(topic),
+                Some(ValueOrArray::Array(topics)) => {
+                    topics.is_empty()
+                        || topics
+                            .iter()
+                            .any(|topic| topic.is_none() || log_topic == topic.as_ref())
+                }
+            }
+        });
+
+        address_matches && block_matches && topics_match
+    }
+}
+
+/// the transaction, as returned by `eth_getTransactionByHash`, mined in the `block` (hash &
+/// number) if any
+fn transaction(
+    from: Address,
+    tx: &TypedTransaction,
+    hash: H256,
+    signature: Signature,
+    block: Option<(H256, U64)>,
+) -> Transaction {
+    let (transaction_type, max_fee_per_gas, max_priority_fee_per_gas, gas_price) = match tx {
+        TypedTransaction::Eip1559(tx) => {
+            let max_fee = tx.max_fee_per_gas.unwrap_or_default();
+            let priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+            let gas_price = max_fee.min(priority_fee + BASE_FEE_PER_GAS);
+            (2, Some(max_fee), Some(priority_fee), gas_price)
+        }
+        TypedTransaction::Eip2930(tx) => (1, None, None, tx.tx.gas_price.unwrap_or_default()),
+        TypedTransaction::Legacy(tx) => (0, None, None, tx.gas_price.unwrap_or_default()),
+    };
+
+    Transaction {
+        hash,
+        nonce: tx.nonce().copied().unwrap_or_default(),
+        block_hash: block.map(|(hash, _)| hash),
+        block_number: block.map(|(_, number)| number),
+        transaction_index: block.map(|_| U64::zero()),
+        from,
+        to: tx.to().and_then(NameOrAddress::as_address).copied(),
+        value: tx.value().copied().unwrap_or_default(),
+        gas_price: Some(gas_price),
+        gas: tx.gas().copied().unwrap_or_default(),
+        input: tx.data().cloned().unwrap_or_default(),
+        v: signature.v.into(),
+        r: signature.r,
+        s: signature.s,
+        transaction_type: Some(transaction_type.into()),
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        chain_id: tx.chain_id().map(|id| id.as_u64().into()),
+        ..Default::default()
+    }
+}
+
+/// a rough gas cost of a call, for estimates and receipts
+fn gas_used(data: &[u8], logs: &[EmittedLog]) -> U256 {
+    U256::from(21_000 + 16 * data.len() + 25_000 * logs.len())
+}
+
+fn block_hash(number: u64) -> H256 {
+    H256(keccak256(
+        [b"mock block".as_slice(), &number.to_be_bytes()].concat(),
+    ))
+}
+
+fn param<P: DeserializeOwned>(params: &Value, index: usize) -> Result<P, MockError> {
+    let param = params.get(index).cloned().unwrap_or(Value::Null);
+    Ok(serde_json::from_value(param)?)
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<Value, MockError> {
+    Ok(serde_json::to_value(value)?)
+}
+
+fn json_rpc_error(code: i64, message: &str, data: Option<Value>) -> MockError {
+    MockError::JsonRpcError(JsonRpcError {
+        code,
+        message: message.to_owned(),
+        data,
+    })
+}
+
+fn invalid_params(message: impl AsRef<str>) -> MockError {
+    json_rpc_error(-32602, &format!("mock: {}", message.as_ref()), None)
+}
+
+/// a revert, as nodes return them, such that the revert reason can be decoded
+fn revert_error(revert: Revert) -> MockError {
+    let data = Value::String(format!("0x{}", hex::encode(revert.data())));
+    json_rpc_error(3, &revert.message(), Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{TimeZone, Utc};
+    use ethers::{
+        providers::Middleware,
+        types::{TransactionRequest, U256},
+    };
+
+    use super::{ManualClock, MockLedger};
+    use crate::{
+        chunking::{ChunkManifest, CHUNK_MANIFEST_MEDIA_TYPE, MAX_CHUNKS},
+        contracts::eth_did_registry::DidEthRegistry,
+        error::RegistrarError,
+        resolver::ResolutionOptions,
+        types::{
+            input::{ResourceInput, SignedResourceInput},
+            output::PendingResource,
+            query::ResourceQuery,
+        },
+        utils::{did_identity_as_full_did, full_did_into_did_identity, VERI_KEY_DELEGATE_TYPE},
+    };
+
+    const START: u64 = 1_700_000_000;
+
+    fn resource_input(content: &str) -> ResourceInput {
+        ResourceInput {
+            resource_name: String::from("foo"),
+            resource_type: String::from("bar"),
+            content: content.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn query_as_of(did: &str, epoch: u64) -> String {
+        ResourceQuery::builder(did)
+            .unwrap()
+            .resource_name("foo")
+            .resource_type("bar")
+            .version_time(Utc.timestamp_opt(epoch as i64, 0).unwrap())
+            .build()
+            .to_did_url()
+    }
+
+    #[tokio::test]
+    async fn test_write_and_resolve_resources() {
+        let clock = Arc::new(ManualClock::new(START));
+        let ledger = MockLedger::with_clock(clock.clone());
+
+        let signer = ledger.random_signer();
+        let did = did_identity_as_full_did(&signer.address(), ledger.chain_id());
+        let registrar = ledger.registrar(signer);
+        let resolver = ledger.resolver();
+
+        let created_res1 = registrar
+            .create_resource(&did, resource_input("hello world"))
+            .await
+            .unwrap();
+        clock.advance(100);
+        let created_res2 = registrar
+            .create_resource(&did, resource_input("hello world 2"))
+            .await
+            .unwrap();
+
+        // ids are from a global counter, across DIDs
+        let other_signer = ledger.random_signer();
+        let other_did = did_identity_as_full_did(&other_signer.address(), ledger.chain_id());
+        let other_res = ledger
+            .registrar(other_signer)
+            .create_resource(&other_did, resource_input("hello world"))
+            .await
+            .unwrap();
+        assert_eq!(created_res1.metadata.resource_id.as_deref(), Some("1"));
+        assert_eq!(created_res2.metadata.resource_id.as_deref(), Some("2"));
+        assert_eq!(other_res.metadata.resource_id.as_deref(), Some("3"));
+
+        // versions are chained, and created at the clock's time
+        assert_eq!(created_res1.metadata.created.timestamp() as u64, START);
+        assert_eq!(
+            created_res2.metadata.created.timestamp() as u64,
+            START + 100
+        );
+        assert_eq!(
+            created_res2.metadata.previous_version_id,
+            created_res1.metadata.resource_id
+        );
+
+        let resolved_res = resolver
+            .resolve_query(&created_res1.metadata.resource_uri)
+            .await
+            .unwrap();
+        assert_eq!(resolved_res.content, created_res1.content);
+        assert_eq!(
+            resolved_res.metadata.next_version_id,
+            created_res2.metadata.resource_id
+        );
+
+        let query = format!("{did}?resourceName=foo&resourceType=bar");
+        let resolved_res = resolver.resolve_query(&query).await.unwrap();
+        assert_eq!(resolved_res.content, created_res2.content);
+
+        let resolved_res = resolver
+            .resolve_query(&query_as_of(&did, START + 50))
+            .await
+            .unwrap();
+        assert_eq!(resolved_res.content, created_res1.content);
+
+        // before the first version
+        assert!(resolver
+            .resolve_query(&query_as_of(&did, START - 1))
+            .await
+            .is_err());
+
+        // the network may be given by name or hex chain ID, but must be the ledger's
+        let address = format!("{:?}", full_did_into_did_identity(&did));
+        for network in ["local:", "0x7a69:"] {
+            let query = format!("did:ethr:{network}{address}?resourceName=foo&resourceType=bar");
+            assert!(resolver.resolve_query(&query).await.is_ok());
+        }
+        for network in ["0x5:", "goerli:", ""] {
+            let query = format!("did:ethr:{network}{address}?resourceName=foo&resourceType=bar");
+            assert!(resolver.resolve_query(&query).await.is_err());
+        }
+
+        // inputs constructed directly are written as-is, even if the builder would reject them
+        let raw_input = ResourceInput {
+            resource_name: String::from("my schema/1.0"),
+            resource_type: String::from("bar"),
+            media_type: String::from("not a media type"),
+            content: b"hello world".to_vec(),
+            ..Default::default()
+        };
+        assert!(raw_input.validate().is_err());
+        let created_res = registrar.create_resource(&did, raw_input).await.unwrap();
+        assert_eq!(created_res.metadata.resource_name, "my schema/1.0");
+        assert_eq!(created_res.metadata.media_type, "not a media type");
+    }
+
+    #[tokio::test]
+    async fn test_owner_and_delegate_checks() {
+        let clock = Arc::new(ManualClock::new(START));
+        let ledger = MockLedger::with_clock(clock.clone());
+        let did_registry = DidEthRegistry::new(ledger.did_ethr_network_config());
+
+        let controller = ledger.random_signer();
+        let delegate = ledger.random_signer();
+        let did = did_identity_as_full_did(&controller.address(), ledger.chain_id());
+
+        // not the controller
+        let err = ledger
+            .registrar(delegate.clone())
+            .create_resource(&did, resource_input("hello world"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            RegistrarError::Unauthorized { signer, current_controller, .. }
+                if signer == Some(delegate.address()) && current_controller == controller.address()
+        ));
+
+        // a delegate, until its validity expires
+        let mut delegate_registrar = ledger.registrar(delegate.clone());
+        delegate_registrar.set_delegate_type(Some(VERI_KEY_DELEGATE_TYPE));
+        did_registry
+            .add_delegate(
+                controller.clone(),
+                &did,
+                VERI_KEY_DELEGATE_TYPE,
+                delegate.address(),
+                3600,
+            )
+            .await;
+        delegate_registrar
+            .create_resource(&did, resource_input("hello world"))
+            .await
+            .unwrap();
+
+        clock.advance(3600);
+        assert!(delegate_registrar
+            .create_resource(&did, resource_input("hello world 2"))
+            .await
+            .is_err());
+
+        // after a change of owner, only the new owner can write
+        let new_controller = ledger.random_signer();
+        did_registry
+            .change_owner(controller.clone(), &did, new_controller.address())
+            .await;
+        assert!(ledger
+            .registrar(controller)
+            .create_resource(&did, resource_input("hello world 2"))
+            .await
+            .is_err());
+        ledger
+            .registrar(new_controller)
+            .create_resource(&did, resource_input("hello world 2"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signed_writes_and_deactivation() {
+        let ledger = MockLedger::new();
+
+        let controller = ledger.random_signer();
+        let relayer = ledger.random_signer();
+        let did = did_identity_as_full_did(&controller.address(), ledger.chain_id());
+        let registrar = ledger.registrar(relayer);
+
+        // relayed
+        let nonce = registrar
+            .get_signed_write_nonce(controller.address())
+            .await
+            .unwrap();
+        let signed_input = SignedResourceInput::sign(
+            controller.signer(),
+            &ledger.dlr_network_config(),
+            &did,
+            nonce,
+            resource_input("hello world"),
+        )
+        .unwrap();
+        let created_res = registrar
+            .create_resource_signed(signed_input.clone())
+            .await
+            .unwrap();
+        assert!(registrar
+            .create_resource_signed(signed_input)
+            .await
+            .is_err());
+
+        // deactivated by the controller
+        let registrar = ledger.registrar(controller);
+        let tombstone = registrar
+            .deactivate_resource(&did, "foo", "bar")
+            .await
+            .unwrap();
+        assert!(tombstone.metadata.deactivated);
+        let err = registrar
+            .deactivate_resource(&did, "foo", "bar")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("already_deactivated"));
+
+        let resolver = ledger.resolver();
+        let query = format!("{did}?resourceName=foo&resourceType=bar");
+        assert!(resolver.resolve_query(&query).await.is_err());
+        let options = ResolutionOptions {
+            include_deactivated: true,
+            ..Default::default()
+        };
+        let resolved_res = resolver
+            .resolve_query_with_options(&query, &options)
+            .await
+            .unwrap();
+        assert_eq!(resolved_res.content, created_res.content);
+    }
+
+    #[tokio::test]
+    async fn test_bump_resource_fee() {
+        let ledger = MockLedger::new();
+        ledger.set_automine(false);
+
+        let signer = ledger.random_signer();
+        let did = did_identity_as_full_did(&signer.address(), ledger.chain_id());
+        let registrar = ledger.registrar(signer.clone());
+
+        let pending = registrar
+            .submit_resource(&did, resource_input("hello world"))
+            .await
+            .unwrap();
+
+        // only the sender's transactions can be replaced
+        let err = ledger
+            .registrar(ledger.random_signer())
+            .bump_resource_fee(&pending, 20)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not the registrar's signer"));
+
+        let bumped = registrar.bump_resource_fee(&pending, 20).await.unwrap();
+        assert_eq!(bumped.nonce, pending.nonce);
+        let tx = signer.get_transaction(pending.tx_hash).await.unwrap();
+        assert!(tx.is_none(), "the replaced transaction is dropped");
+        let bumped_tx = signer
+            .get_transaction(bumped.tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bumped_tx.transaction_type, Some(2.into()));
+
+        ledger.mine_pending();
+        let resource = registrar.await_resource(bumped.tx_hash).await.unwrap();
+        assert_eq!(resource.content, b"hello world");
+
+        // legacy transactions are replaced by legacy transactions
+        let legacy_tx = TransactionRequest::new().to(signer.address()).value(1);
+        let legacy_hash = signer
+            .send_transaction(legacy_tx, None)
+            .await
+            .unwrap()
+            .tx_hash();
+        let legacy_tx = signer.get_transaction(legacy_hash).await.unwrap().unwrap();
+        let pending = PendingResource {
+            tx_hash: legacy_hash,
+            nonce: legacy_tx.nonce,
+        };
+        let bumped = registrar.bump_resource_fee(&pending, 20).await.unwrap();
+        let bumped_tx = signer
+            .get_transaction(bumped.tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(bumped_tx.transaction_type, Some(0.into()));
+        assert_eq!(bumped_tx.max_fee_per_gas, None);
+        assert_eq!(
+            bumped_tx.gas_price,
+            Some(legacy_tx.gas_price.unwrap() * U256::from(120) / U256::from(100) + 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resend_dropped_transaction() {
+        let ledger = MockLedger::new();
+        ledger.set_automine(false);
+
+        let signer = ledger.random_signer();
+        let did = did_identity_as_full_did(&signer.address(), ledger.chain_id());
+        let registrar = ledger.registrar(signer);
+
+        let mut pending = vec![];
+        for content in ["hello world 1", "hello world 2", "hello world 3"] {
+            let pending_resource = registrar
+                .submit_resource(&did, resource_input(content))
+                .await
+                .unwrap();
+            pending.push(pending_resource);
+        }
+
+        // the middle transaction is dropped, so the last is stuck behind the nonce gap
+        assert!(ledger.drop_transaction(pending[1].tx_hash));
+        ledger.set_automine(true);
+        ledger.mine_pending();
+
+        let resource = registrar.await_resource(pending[0].tx_hash).await.unwrap();
+        assert_eq!(resource.content, b"hello world 1");
+        // re-sent when awaited, filling the gap
+        let resource = registrar.await_resource(pending[1].tx_hash).await.unwrap();
+        assert_eq!(resource.content, b"hello world 2");
+        let resource = registrar.await_resource(pending[2].tx_hash).await.unwrap();
+        assert_eq!(resource.content, b"hello world 3");
+        assert_eq!(
+            resource.metadata.previous_version_id,
+            Some(String::from("2"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_malformed_chunk_manifests() {
+        let ledger = MockLedger::new();
+        let signer = ledger.random_signer();
+        let did = did_identity_as_full_did(&signer.address(), ledger.chain_id());
+        let registrar = ledger.registrar(signer);
+        let resolver = ledger.resolver();
+        let query = format!("{did}?resourceName=foo&resourceType=bar");
+
+        let manifest = |chunk_ids: Vec<String>| ResourceInput {
+            media_type: CHUNK_MANIFEST_MEDIA_TYPE.to_owned(),
+            content: serde_json::to_vec(&ChunkManifest {
+                chunk_ids,
+                content_hash: String::new(),
+                media_type: String::from("text/plain"),
+            })
+            .unwrap(),
+            ..resource_input("")
+        };
+
+        // written by a third party, the chunk ids may not be resource ids at all, or too many
+        for chunk_ids in [
+            vec![String::from("x")],
+            vec![String::from("1"); MAX_CHUNKS + 1],
+        ] {
+            // written, but the written resource fails to resolve
+            assert!(registrar
+                .create_resource(&did, manifest(chunk_ids))
+                .await
+                .is_err());
+            assert!(resolver.resolve_query(&query).await.is_err());
+        }
+    }
+}
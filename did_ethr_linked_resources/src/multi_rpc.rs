@@ -0,0 +1,387 @@
+//! Reading from several RPC endpoints, with failover or quorum (see [MultiRpcConfig]).
+//!
+//! ```ignore
+//! let client = MultiRpcClient::new(&MultiRpcConfig::new(
+//!     rpc_urls,
+//!     ReadPolicy::Quorum { min_agreeing: 2 },
+//! ))?;
+//! let resolver = EthrDidLinkedResourcesResolver::with_client(config, Arc::new(Provider::new(client)));
+//! ```
+
+use std::{error::Error, fmt::Debug, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ethers::{
+    providers::{
+        Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, Quorum, QuorumError,
+        QuorumProvider, RpcError, WeightedProvider,
+    },
+    types::U64,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::{MultiRpcConfig, ReadPolicy, RetryPolicy};
+
+/// A single RPC endpoint, retrying transient failures with exponential backoff.
+#[derive(Clone, Debug)]
+pub struct RpcEndpoint {
+    url: String,
+    http: Arc<Http>,
+    retry_policy: RetryPolicy,
+}
+
+impl RpcEndpoint {
+    pub fn new(url: &str, retry_policy: RetryPolicy) -> Result<Self, Box<dyn Error>> {
+        let client = reqwest::Client::builder()
+            .timeout(retry_policy.request_timeout)
+            .build()?;
+        let http = Arc::new(Http::new_with_client(url::Url::parse(url)?, client));
+
+        Ok(Self {
+            url: url.to_owned(),
+            http,
+            retry_policy,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcEndpoint {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // serialize once to re-send on retries. Zero sized params (`()`) must stay zero sized,
+        // as they are omitted from the request
+        let params = if std::mem::size_of::<T>() == 0 {
+            None
+        } else {
+            Some(serde_json::to_value(params)?)
+        };
+
+        let mut backoff = self.retry_policy.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let result = match &params {
+                Some(params) => self.http.request(method, params).await,
+                None => self.http.request(method, ()).await,
+            };
+
+            match result {
+                Err(e) if retries < self.retry_policy.max_retries && is_transient(&e) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff = Duration::min(backoff * 2, self.retry_policy.max_backoff);
+                    retries += 1;
+                }
+                result => return result.map_err(Into::into),
+            }
+        }
+    }
+}
+
+/// Whether the endpoint failed to answer (rather than answering with a JSON-RPC error), such
+/// that the request may succeed if retried (or sent to another endpoint).
+fn is_transient(error: &HttpClientError) -> bool {
+    match error {
+        HttpClientError::ReqwestError(_) | HttpClientError::SerdeJson { .. } => true,
+        HttpClientError::JsonRpcError(e) => is_rate_limited(e),
+    }
+}
+
+fn is_rate_limited(error: &JsonRpcError) -> bool {
+    error.code == 429 || error.code == -32005 || error.message.contains("rate limit")
+}
+
+/// [is_transient], for the errors of an [RpcEndpoint] (after its retries)
+fn is_transient_provider_error(error: &ProviderError) -> bool {
+    error.as_error_response().is_none_or(is_rate_limited)
+}
+
+/// The result of a [MultiRpcClient::check_health] of an endpoint.
+#[derive(Debug)]
+pub struct EndpointHealth {
+    pub url: String,
+    /// The endpoint's latest block number, or why it could not be fetched.
+    pub block_number: Result<U64, ProviderError>,
+}
+
+/// A [JsonRpcClient] reading from several endpoints, according to the [ReadPolicy] of its
+/// [MultiRpcConfig]. Wrap in a [ethers::providers::Provider] and pass to `with_client` of the
+/// registry or resolver.
+#[derive(Debug)]
+pub enum MultiRpcClient {
+    Failover(Vec<RpcEndpoint>),
+    Quorum {
+        endpoints: Vec<RpcEndpoint>,
+        quorum: QuorumProvider<RpcEndpoint>,
+        min_agreeing: usize,
+    },
+}
+
+impl MultiRpcClient {
+    pub fn new(config: &MultiRpcConfig) -> Result<Self, Box<dyn Error>> {
+        if config.rpc_urls.is_empty() {
+            return Err("No RPC endpoints configured".into());
+        }
+
+        let endpoints = config
+            .rpc_urls
+            .iter()
+            .map(|url| RpcEndpoint::new(url, config.retry_policy.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match config.read_policy {
+            ReadPolicy::Failover => Ok(Self::Failover(endpoints)),
+            ReadPolicy::Quorum { min_agreeing } => {
+                if min_agreeing == 0 || min_agreeing > endpoints.len() {
+                    return Err(format!(
+                        "Quorum of {min_agreeing} is unreachable with {} endpoints",
+                        endpoints.len()
+                    )
+                    .into());
+                }
+
+                let quorum = QuorumProvider::new(
+                    Quorum::ProviderCount(min_agreeing),
+                    endpoints.iter().cloned().map(WeightedProvider::new),
+                );
+                Ok(Self::Quorum {
+                    endpoints,
+                    quorum,
+                    min_agreeing,
+                })
+            }
+        }
+    }
+
+    pub fn endpoints(&self) -> &[RpcEndpoint] {
+        match self {
+            Self::Failover(endpoints) | Self::Quorum { endpoints, .. } => endpoints,
+        }
+    }
+
+    /// Fetch the latest block number of each endpoint, e.g. to alert on endpoints which are down
+    /// or lagging.
+    pub async fn check_health(&self) -> Vec<EndpointHealth> {
+        let mut health = vec![];
+        for endpoint in self.endpoints() {
+            health.push(EndpointHealth {
+                url: endpoint.url().to_owned(),
+                block_number: endpoint.request("eth_blockNumber", ()).await,
+            });
+        }
+        health
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for MultiRpcClient {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Failover(endpoints) => {
+                let params = if std::mem::size_of::<T>() == 0 {
+                    None
+                } else {
+                    Some(serde_json::to_value(params)?)
+                };
+
+                let mut last_error = None;
+                for endpoint in endpoints {
+                    let result = match &params {
+                        Some(params) => endpoint.request(method, params).await,
+                        None => endpoint.request(method, ()).await,
+                    };
+                    match result {
+                        Err(e) if is_transient_provider_error(&e) => last_error = Some(e),
+                        result => return result,
+                    }
+                }
+                // endpoints is never empty
+                Err(last_error.unwrap())
+            }
+            Self::Quorum {
+                quorum,
+                min_agreeing,
+                ..
+            } => quorum
+                .request(method, params)
+                .await
+                .map_err(|e| agreed_error(e, *min_agreeing)),
+        }
+    }
+}
+
+/// If a quorum was not reached because the endpoints agreed on a JSON-RPC error (e.g. a revert),
+/// return that error rather than the opaque [QuorumError], so that it can be decoded.
+fn agreed_error(error: ProviderError, min_agreeing: usize) -> ProviderError {
+    let ProviderError::JsonRpcClientError(inner) = &error else {
+        return error;
+    };
+    let inner: &(dyn Error + 'static) = inner.as_ref();
+    let Some(QuorumError::NoQuorumReached { errors, .. }) = inner.downcast_ref() else {
+        return error;
+    };
+
+    let json_rpc_errors = errors
+        .iter()
+        .filter_map(|e| e.as_error_response())
+        .collect::<Vec<_>>();
+    let agreed = json_rpc_errors.iter().find(|e| {
+        json_rpc_errors
+            .iter()
+            .filter(|other| same_json_rpc_error(e, other))
+            .count()
+            >= min_agreeing
+    });
+
+    match agreed {
+        Some(agreed) => ProviderError::JsonRpcClientError(Box::new(HttpClientError::JsonRpcError(
+            (*agreed).clone(),
+        ))),
+        None => error,
+    }
+}
+
+fn same_json_rpc_error(a: &JsonRpcError, b: &JsonRpcError) -> bool {
+    a.code == b.code && a.message == b.message && a.data == b.data
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use ethers::{
+        providers::{Middleware, Provider},
+        types::{Address, H160, U256},
+    };
+
+    use crate::{
+        config::{MultiRpcConfig, ReadPolicy, RetryPolicy},
+        contracts::{
+            ethr_dlr_registry::{EthrDIDLinkedResourcesRegistry, EthrDLRRegistry},
+            registry_version::RegistryVersion,
+            test_utils::TestConfig,
+        },
+    };
+
+    use super::MultiRpcClient;
+
+    // nothing listens on port 1
+    const DEAD_RPC_URL: &str = "http://127.0.0.1:1";
+
+    fn fast_retries() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            request_timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn test_invalid_multi_rpc_configs() {
+        assert!(MultiRpcClient::new(&MultiRpcConfig::new(vec![], ReadPolicy::Failover)).is_err());
+        assert!(MultiRpcClient::new(&MultiRpcConfig::new(
+            vec![DEAD_RPC_URL.to_owned()],
+            ReadPolicy::Quorum { min_agreeing: 2 }
+        ))
+        .is_err());
+        assert!(MultiRpcClient::new(&MultiRpcConfig::new(
+            vec!["not a url".to_owned()],
+            ReadPolicy::Failover
+        ))
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failover_fails_when_all_endpoints_are_down() {
+        let mut config = MultiRpcConfig::new(
+            vec![DEAD_RPC_URL.to_owned(), DEAD_RPC_URL.to_owned()],
+            ReadPolicy::Failover,
+        );
+        config.retry_policy = fast_retries();
+        let provider = Provider::new(MultiRpcClient::new(&config).unwrap());
+
+        assert!(provider.get_block_number().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_health_checks() {
+        let conf = TestConfig::load();
+
+        let mut rpc_urls = vec![DEAD_RPC_URL.to_owned()];
+        rpc_urls.extend(conf.rpc_urls.clone());
+        let mut config = MultiRpcConfig::new(rpc_urls, ReadPolicy::Failover);
+        config.retry_policy = fast_retries();
+        let client = MultiRpcClient::new(&config).unwrap();
+
+        let health = client.check_health().await;
+        assert_eq!(health.len(), conf.rpc_urls.len() + 1);
+        assert!(health[0].block_number.is_err());
+        assert!(health[1..].iter().all(|h| h.block_number.is_ok()));
+
+        // reads fail over to the live endpoints
+        let registry = EthrDIDLinkedResourcesRegistry::with_client(
+            conf.get_dlr_network_config(),
+            Arc::new(Provider::new(client)),
+        );
+        assert_eq!(
+            registry.registry_version().await.unwrap(),
+            RegistryVersion::V2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_quorum_reads() {
+        let conf = TestConfig::load();
+
+        let mut rpc_urls = vec![
+            conf.rpc_url.clone(),
+            conf.rpc_url.clone(),
+            DEAD_RPC_URL.to_owned(),
+        ];
+        let mut config =
+            MultiRpcConfig::new(rpc_urls.clone(), ReadPolicy::Quorum { min_agreeing: 2 });
+        config.retry_policy = fast_retries();
+
+        let registry = EthrDIDLinkedResourcesRegistry::with_client(
+            conf.get_dlr_network_config(),
+            Arc::new(Provider::new(MultiRpcClient::new(&config).unwrap())),
+        );
+        assert_eq!(
+            registry.registry_version().await.unwrap(),
+            RegistryVersion::V2
+        );
+
+        // agreeing on a revert is a quorum (on the revert)
+        let contract = EthrDLRRegistry::new(
+            conf.dlr_contract_address.parse::<Address>().unwrap(),
+            Arc::new(Provider::new(MultiRpcClient::new(&config).unwrap())),
+        );
+        let err = contract
+            .get_resource_metadata_chain_node(H160::zero(), [0; 32], U256::zero())
+            .call()
+            .await
+            .unwrap_err();
+        assert!(err.is_revert());
+
+        // 1 live endpoint can't reach a quorum of 2
+        rpc_urls.remove(0);
+        config.rpc_urls = rpc_urls;
+        let provider = Provider::new(MultiRpcClient::new(&config).unwrap());
+        assert!(provider.get_block_number().await.is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use std::{collections::HashMap, error::Error, sync::Mutex};
+
+use anyhow::anyhow;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, H160, H256, U256},
+};
+
+use crate::telemetry;
+
+/// Hands out sequential transaction nonces for a single sender, so that concurrent writes from
+/// the same signer don't race on the nonce the node reports. The transactions sent with them are
+/// kept until mined, so that they can be re-sent if dropped.
+#[derive(Debug, Default)]
+pub(crate) struct NonceManager {
+    next_nonce: Mutex<Option<U256>>,
+    sent_transactions: Mutex<HashMap<H256, TypedTransaction>>,
+}
+
+impl NonceManager {
+    /// Allocate the next nonce for `sender`, syncing with the node's pending transaction count
+    /// if no nonce is currently tracked.
+    pub async fn next<M: Middleware>(
+        &self,
+        client: &M,
+        sender: H160,
+    ) -> Result<U256, Box<dyn Error>> {
+        let tracked_nonce = self.take_next();
+        telemetry::cache_lookup("nonce", tracked_nonce.is_some());
+        if let Some(nonce) = tracked_nonce {
+            return Ok(nonce);
+        }
+
+        let pending_count = client
+            .get_transaction_count(sender, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        let mut next_nonce = self.next_nonce.lock().unwrap();
+        // another write may have synced while the count was being fetched
+        let nonce = next_nonce.unwrap_or(pending_count);
+        *next_nonce = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Forget the tracked nonce, so that the next allocation resyncs with the node.
+    pub fn reset(&self) {
+        *self.next_nonce.lock().unwrap() = None;
+    }
+
+    /// Give back the allocated `nonce` of a write which failed before it was broadcast. It is
+    /// only reallocated if no later nonce has been allocated since (to a concurrent write).
+    /// Returns whether it was, otherwise the nonce is a gap which blocks the signer's later
+    /// transactions, and must be filled by the caller.
+    pub fn release(&self, nonce: U256) -> bool {
+        let mut next_nonce = self.next_nonce.lock().unwrap();
+        if *next_nonce == Some(nonce + 1) {
+            *next_nonce = Some(nonce);
+            return true;
+        }
+        false
+    }
+
+    /// Keep the transaction `tx` (sent as `tx_hash`), until [Self::take_sent].
+    pub fn record_sent(&self, tx_hash: H256, tx: TypedTransaction) {
+        self.sent_transactions.lock().unwrap().insert(tx_hash, tx);
+    }
+
+    /// Take the transaction sent as `tx_hash` (see [Self::record_sent]), e.g. once it is mined.
+    pub fn take_sent(&self, tx_hash: H256) -> Option<TypedTransaction> {
+        self.sent_transactions.lock().unwrap().remove(&tx_hash)
+    }
+
+    fn take_next(&self) -> Option<U256> {
+        let mut next_nonce = self.next_nonce.lock().unwrap();
+        let nonce = (*next_nonce)?;
+        *next_nonce = Some(nonce + 1);
+        Some(nonce)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{H160, U256},
+    };
+
+    use super::NonceManager;
+
+    #[tokio::test]
+    async fn test_release() {
+        let transport = MockProvider::new();
+        transport.push(U256::from(5)).unwrap();
+        let client = Provider::new(transport);
+        let nonce_manager = NonceManager::default();
+        let sender = H160::zero();
+
+        let first = nonce_manager.next(&client, sender).await.unwrap();
+        let second = nonce_manager.next(&client, sender).await.unwrap();
+        assert_eq!((first, second), (5.into(), 6.into()));
+
+        // the last allocated nonce is reallocated
+        assert!(nonce_manager.release(second));
+        assert_eq!(nonce_manager.next(&client, sender).await.unwrap(), second);
+
+        // an earlier one is left as a gap (for the caller to fill)
+        assert!(!nonce_manager.release(first));
+        assert_eq!(nonce_manager.next(&client, sender).await.unwrap(), 7.into());
+    }
+}
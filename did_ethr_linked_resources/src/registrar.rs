@@ -1,19 +1,66 @@
 use std::{error::Error, sync::Arc};
 
-use ethers::providers::Middleware;
+use anyhow::anyhow;
+use ethers::{
+    contract::{ContractCall, ContractError},
+    providers::{Middleware, PendingTransaction},
+    types::{
+        transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest,
+        TransactionRequest, H160, H256, U256, U64,
+    },
+};
+use semver::Version;
 
 use crate::{
-    config::ContractNetworkConfig, contracts::ethr_dlr_registry::EthrDIDLinkedResourcesRegistry,
-    types::output::Resource, utils::ChainId,
+    chunking::{chunk_resource_inputs, manifest_resource_input, ChunkedUpload, MAX_CHUNKS},
+    config::{ContractNetworkConfig, FeeStrategy, PriorityFeePolicy, VersionPolicy},
+    content_encoding::encode_resource_input,
+    content_store::{self, ContentStore},
+    contracts::{
+        ethr_dlr_registry::{
+            decode_contract_error, decode_new_resource_events, EthrDIDLinkedResourcesRegistry,
+            NewResourceFilter, TOMBSTONE_MEDIA_TYPE,
+        },
+        registry_version::RegistryVersion,
+    },
+    error::{RegistrarError, RegistryRevert, ResourceInputError},
+    nonce_manager::NonceManager,
+    telemetry::{self, HttpProvider},
+    types::output::{PendingResource, Resource, ResourceCostEstimate},
+    utils::ChainId,
+};
+
+use super::{
+    resolver::EthrDidLinkedResourcesResolver,
+    types::input::{ResourceInput, SignedResourceInput},
 };
 
-use super::{resolver::EthrDidLinkedResourcesResolver, types::input::ResourceInput};
+/// Options for creating resources, see
+/// [EthrDidLinkedResourcesRegistrar::create_resource_with_options].
+#[derive(Clone, Debug, Default)]
+pub struct CreateResourceOptions {
+    /// If the latest version of the resource (by name & type) has the same content, media type
+    /// and version id, return it rather than writing a new (identical) version.
+    pub if_changed: bool,
+}
 
-pub struct EthrDidLinkedResourcesRegistrar<S> {
-    registry: EthrDIDLinkedResourcesRegistry,
-    resolver: EthrDidLinkedResourcesResolver, // eh - only need this for the metadata node convenience method
+/// minimum fee increase for replacement transactions accepted by most nodes
+const MIN_FEE_BUMP_PERCENTAGE: u64 = 10;
+
+pub struct EthrDidLinkedResourcesRegistrar<S, M = HttpProvider> {
+    registry: EthrDIDLinkedResourcesRegistry<M>,
+    resolver: EthrDidLinkedResourcesResolver<M>, // eh - only need this for the metadata node convenience method
     signer: Arc<S>,
     chain_id: u64,
+    /// if set, the signer writes as a did:ethr delegate of this type, rather than as the DID controller
+    delegate_type: Option<String>,
+    fee_strategy: FeeStrategy,
+    dry_run_before_send: bool,
+    version_policy: VersionPolicy,
+    /// if set, resource content is stored here rather than on the ledger
+    content_store: Option<Arc<dyn ContentStore>>,
+    /// nonces of the signer's transactions, allocated locally so that concurrent writes don't race
+    nonce_manager: NonceManager,
 }
 
 impl<S> EthrDidLinkedResourcesRegistrar<S>
@@ -21,37 +68,1032 @@ where
     S: Middleware,
 {
     pub fn new(signer: Arc<S>, config: ContractNetworkConfig) -> Self {
+        // the registry and resolver share a provider for reads
+        let provider = Arc::new(telemetry::http_provider(&config.rpc_url).unwrap());
+        Self::with_client(signer, config, provider)
+    }
+
+    /// Create a registrar where the `signer` writes resources as a delegate of the DID
+    /// (of type `delegate_type`, e.g. [crate::utils::VERI_KEY_DELEGATE_TYPE]), rather than as
+    /// the DID controller. The delegate must be added to the `EthereumDIDRegistry` by the controller.
+    pub fn new_as_delegate(
+        signer: Arc<S>,
+        config: ContractNetworkConfig,
+        delegate_type: &str,
+    ) -> Self {
+        let mut registrar = Self::new(signer, config);
+        registrar.set_delegate_type(Some(delegate_type));
+        registrar
+    }
+}
+
+impl<S, M> EthrDidLinkedResourcesRegistrar<S, M>
+where
+    S: Middleware,
+    M: Middleware + 'static,
+{
+    /// Create a registrar which reads from the registry with the given `client` (shared by its
+    /// registry and resolver), rather than a HTTP provider for the `config.rpc_url`.
+    pub fn with_client(signer: Arc<S>, config: ContractNetworkConfig, client: Arc<M>) -> Self {
         Self {
             chain_id: config.chain_id,
-            registry: EthrDIDLinkedResourcesRegistry::new(config.clone()),
-            resolver: EthrDidLinkedResourcesResolver::new(config),
+            registry: EthrDIDLinkedResourcesRegistry::with_client(config.clone(), client.clone()),
+            resolver: EthrDidLinkedResourcesResolver::with_client(config, client),
             signer,
+            delegate_type: None,
+            fee_strategy: FeeStrategy::default(),
+            dry_run_before_send: false,
+            version_policy: VersionPolicy::default(),
+            content_store: None,
+            nonce_manager: NonceManager::default(),
         }
     }
 
     pub fn change_signer(&mut self, new_signer: Arc<S>) {
         self.signer = new_signer;
+        self.nonce_manager = NonceManager::default();
     }
 
+    /// Write resources as a delegate of the DID of type `delegate_type` (see
+    /// [EthrDidLinkedResourcesRegistrar::new_as_delegate]), or as the DID controller if `None`.
+    pub fn set_delegate_type(&mut self, delegate_type: Option<&str>) {
+        self.delegate_type = delegate_type.map(str::to_owned);
+    }
+
+    /// Set which version ids are permitted for new versions of resources written by this
+    /// registrar. Checked against the existing versions before sending.
+    pub fn set_version_policy(&mut self, version_policy: VersionPolicy) {
+        self.version_policy = version_policy;
+    }
+
+    /// Store the content of resources written by this registrar in the `content_store`, writing
+    /// only a reference to the content (URI & hash) to the ledger. The registrar's resolver can
+    /// then also fetch content from this store.
+    pub fn set_content_store(&mut self, content_store: Arc<dyn ContentStore>) {
+        self.resolver.add_content_store(content_store.clone());
+        self.content_store = Some(content_store);
+    }
+
+    /// Resync the signer's locally tracked transaction nonce with the node, e.g. if the signer's
+    /// key is also used elsewhere, or if a transaction which this registrar can't re-send was
+    /// dropped by the network (see [Self::await_resource]). Should not be called while writes
+    /// are in flight, as their nonces may then be allocated again.
+    pub fn resync_nonce(&self) {
+        self.nonce_manager.reset();
+    }
+
+    /// Set how fees are chosen for transactions sent by this registrar.
+    pub fn set_fee_strategy(&mut self, fee_strategy: FeeStrategy) {
+        self.fee_strategy = fee_strategy;
+    }
+
+    /// If enabled, every write is simulated (via `eth_call`) before it is sent, so that reverts
+    /// are caught before paying for a transaction.
+    pub fn set_dry_run_before_send(&mut self, dry_run_before_send: bool) {
+        self.dry_run_before_send = dry_run_before_send;
+    }
+
+    /// Create a resource for the `did`, signed by the registrar's signer, and wait for it to be
+    /// written to the ledger.
+    ///
+    /// Before sending, the signer is checked to be the current controller of the DID (or a valid
+    /// delegate, if this registrar writes as a delegate), failing with
+    /// [RegistrarError::Unauthorized] if not.
     pub async fn create_resource(
         &self,
         did: &str,
         resource_input: ResourceInput,
-    ) -> Result<Resource, Box<dyn Error>> {
-        let resource = self
+    ) -> Result<Resource, RegistrarError> {
+        self.create_resource_with_options(did, resource_input, &CreateResourceOptions::default())
+            .await
+    }
+
+    /// Like [Self::create_resource], with [CreateResourceOptions].
+    pub async fn create_resource_with_options(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+        options: &CreateResourceOptions,
+    ) -> Result<Resource, RegistrarError> {
+        telemetry::write("create_resource", Some(did), async {
+            if options.if_changed {
+                if let Some(latest) = self.find_unchanged_resource(did, &resource_input).await? {
+                    return Ok(latest);
+                }
+            }
+
+            let pending_resource = self.submit_resource_input(did, resource_input).await?;
+            self.await_written_resource(pending_resource.tx_hash).await
+        })
+        .await
+    }
+
+    /// Create all `resource_inputs` for the `did` atomically, in a single transaction (saving
+    /// on per-transaction fees), and wait for them to be written to the ledger.
+    ///
+    /// The created resources are returned in the same order as `resource_inputs`.
+    pub async fn create_resources(
+        &self,
+        did: &str,
+        resource_inputs: Vec<ResourceInput>,
+    ) -> Result<Vec<Resource>, RegistrarError> {
+        telemetry::write("create_resources", Some(did), async {
+            if resource_inputs.is_empty() {
+                return Ok(vec![]);
+            }
+
+            let signer = self.signer.default_sender();
+            if let Some(signer) = signer {
+                self.ensure_authorized(did, signer, self.delegate_type.as_deref())
+                    .await?;
+            }
+
+            for (i, resource_input) in resource_inputs.iter().enumerate() {
+                self.validate_resource_input(did, resource_input, &resource_inputs[..i])
+                    .await?;
+            }
+
+            let mut prepared_inputs = vec![];
+            for resource_input in resource_inputs {
+                prepared_inputs.push(self.prepare_resource_input(resource_input).await?);
+            }
+            let resource_inputs = prepared_inputs;
+
+            let call = match &self.delegate_type {
+                Some(delegate_type) => self.registry.create_resources_as_delegate_call(
+                    self.signer.clone(),
+                    did,
+                    delegate_type,
+                    resource_inputs,
+                )?,
+                None => {
+                    self.registry
+                        .create_resources_call(self.signer.clone(), did, resource_inputs)
+                }
+            };
+            let pending_resource = self.submit_create_resource_call(did, signer, call).await?;
+            self.await_written_resources(pending_resource.tx_hash).await
+        })
+        .await
+    }
+
+    /// Create a resource for the `did` whose content may not fit in a single transaction (e.g.
+    /// due to block gas limits). If the content to write to the ledger is larger than
+    /// `chunk_size` bytes, it is written in chunks (a transaction each), followed by a manifest
+    /// resource which links the chunks. The manifest is the created resource, and is resolved
+    /// with the assembled (and verified) content.
+    ///
+    /// `upload` tracks the progress of the upload, and `on_progress` is called after each chunk
+    /// is written. If the upload fails part way, calling again with the same `upload` (and
+    /// resource input) resumes from the first chunk which was not written.
+    pub async fn create_resource_chunked(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+        chunk_size: usize,
+        upload: &mut ChunkedUpload,
+        mut on_progress: impl FnMut(&ChunkedUpload),
+    ) -> Result<Resource, RegistrarError> {
+        telemetry::write("create_resource_chunked", Some(did), async {
+            if chunk_size == 0 {
+                return Err(RegistrarError::Other("Chunk size must be non-zero".into()));
+            }
+
+            let signer = self.signer.default_sender();
+            if let Some(signer) = signer {
+                self.ensure_authorized(did, signer, self.delegate_type.as_deref())
+                    .await?;
+            }
+
+            self.validate_resource_input(did, &resource_input, &[])
+                .await?;
+
+            let resource_input = self.prepare_resource_input(resource_input).await?;
+            if resource_input.content.len() <= chunk_size {
+                let call = self.resource_call(did, resource_input)?;
+                let pending_resource = self.submit_create_resource_call(did, signer, call).await?;
+                return self.await_written_resource(pending_resource.tx_hash).await;
+            }
+
+            let chunks = chunk_resource_inputs(&resource_input, chunk_size);
+            if chunks.len() > MAX_CHUNKS {
+                return Err(RegistrarError::Other(
+                    format!(
+                        "The content would be written in {} chunks, more than the maximum of \
+                         {MAX_CHUNKS}, use a larger chunk size",
+                        chunks.len()
+                    )
+                    .into(),
+                ));
+            }
+            let content_hash = content_store::content_hash(&resource_input.content);
+            if upload.written_chunk_ids.is_empty() {
+                *upload = ChunkedUpload {
+                    content_hash,
+                    total_chunks: chunks.len(),
+                    written_chunk_ids: vec![],
+                };
+            } else if upload.content_hash != content_hash || upload.total_chunks != chunks.len() {
+                return Err(RegistrarError::Other(
+                    "The upload is of different content (or chunk size), cannot resume".into(),
+                ));
+            }
+
+            let written_chunks = upload.written_chunk_ids.len();
+            for chunk in chunks.into_iter().skip(written_chunks) {
+                let call = self.resource_call(did, chunk)?;
+                let pending_resource = self.submit_create_resource_call(did, signer, call).await?;
+                let chunk_resource = self
+                    .await_written_resource(pending_resource.tx_hash)
+                    .await?;
+
+                let chunk_id = chunk_resource.metadata.resource_id.ok_or_else(|| {
+                    RegistrarError::Other("The written chunk has no resource id".into())
+                })?;
+                upload.written_chunk_ids.push(chunk_id);
+                on_progress(upload);
+            }
+
+            let manifest =
+                manifest_resource_input(resource_input, upload.written_chunk_ids.clone())?;
+            let call = self.resource_call(did, manifest)?;
+            let pending_resource = self.submit_create_resource_call(did, signer, call).await?;
+            self.await_written_resource(pending_resource.tx_hash).await
+        })
+        .await
+    }
+
+    /// Deactivate the resource of the `did` (known by name & type), by appending a tombstone
+    /// version, signalling that it should no longer be used. Resolving the resource by name &
+    /// type then fails (unless history is requested, in which case the resolved version is
+    /// flagged as deactivated). Writing a new version of the resource afterwards reactivates it.
+    ///
+    /// Returns the tombstone version.
+    pub async fn deactivate_resource(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<Resource, RegistrarError> {
+        telemetry::write("deactivate_resource", Some(did), async {
+            let signer = self.signer.default_sender();
+            if let Some(signer) = signer {
+                self.ensure_authorized(did, signer, self.delegate_type.as_deref())
+                    .await?;
+            }
+
+            let call = match &self.delegate_type {
+                Some(delegate_type) => self.registry.deactivate_resource_as_delegate_call(
+                    self.signer.clone(),
+                    did,
+                    delegate_type,
+                    resource_name,
+                    resource_type,
+                )?,
+                None => self.registry.deactivate_resource_call(
+                    self.signer.clone(),
+                    did,
+                    resource_name,
+                    resource_type,
+                ),
+            };
+            let pending_resource = self.submit_create_resource_call(did, signer, call).await?;
+            self.await_written_resource(pending_resource.tx_hash).await
+        })
+        .await
+    }
+
+    /// Like [Self::create_resource], but returns as soon as the transaction is broadcast. The
+    /// returned [PendingResource] can be persisted, and the resource later retrieved with
+    /// [Self::await_resource] (e.g. after a restart).
+    pub async fn submit_resource(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+    ) -> Result<PendingResource, RegistrarError> {
+        telemetry::write(
+            "submit_resource",
+            Some(did),
+            self.submit_resource_input(did, resource_input),
+        )
+        .await
+    }
+
+    /// Wait for the resource writing transaction `tx_hash` (see [Self::submit_resource]) to be
+    /// mined, and return the created resource. Resolves immediately if it is already mined.
+    ///
+    /// If the transaction is dropped by the network, it is re-sent (with the same nonce) if it
+    /// was sent by this registrar, so that it doesn't leave a gap blocking the signer's later
+    /// transactions. Otherwise (e.g. after a restart) this fails, and the gap is filled by the
+    /// next write of a registrar which has not allocated any nonces yet.
+    pub async fn await_resource(&self, tx_hash: H256) -> Result<Resource, RegistrarError> {
+        telemetry::write("await_resource", None, self.await_written_resource(tx_hash)).await
+    }
+
+    /// Like [Self::await_resource], but returns every resource created by the transaction, e.g.
+    /// for batch writes.
+    pub async fn await_resources(&self, tx_hash: H256) -> Result<Vec<Resource>, RegistrarError> {
+        telemetry::write(
+            "await_resources",
+            None,
+            self.await_written_resources(tx_hash),
+        )
+        .await
+    }
+
+    /// Replace a stuck [PendingResource] transaction with the same transaction (same nonce), but
+    /// with fees increased by `fee_bump_percentage` (at least 10%, as nodes require). The
+    /// replaced transaction will be dropped, so the returned [PendingResource] should be awaited
+    /// instead. A legacy (gas price) transaction is replaced by a legacy transaction.
+    ///
+    /// Fails if the transaction was not sent by the registrar's signer.
+    pub async fn bump_resource_fee(
+        &self,
+        pending_resource: &PendingResource,
+        fee_bump_percentage: u64,
+    ) -> Result<PendingResource, RegistrarError> {
+        telemetry::write("bump_resource_fee", None, async {
+            if fee_bump_percentage < MIN_FEE_BUMP_PERCENTAGE {
+                return Err(RegistrarError::Other(
+                    format!("Fees must be bumped by at least {MIN_FEE_BUMP_PERCENTAGE}%").into(),
+                ));
+            }
+
+            let tx_hash = pending_resource.tx_hash;
+            let tx = self
+                .signer
+                .get_transaction(tx_hash)
+                .await
+                .map_err(|e| RegistrarError::Other(e.to_string().into()))?
+                .ok_or_else(|| {
+                    RegistrarError::Other(format!("Transaction {tx_hash:?} not found").into())
+                })?;
+            if let Some(sender) = self.signer.default_sender() {
+                if tx.from != sender {
+                    return Err(RegistrarError::Other(
+                        format!(
+                            "Transaction {tx_hash:?} was sent by {:?}, not the registrar's signer {sender:?}",
+                            tx.from
+                        )
+                        .into(),
+                    ));
+                }
+            }
+            if tx.block_hash.is_some() {
+                return Err(RegistrarError::Other(
+                    format!("Transaction {tx_hash:?} is already mined").into(),
+                ));
+            }
+
+            let bump = |fee: U256| fee * U256::from(100 + fee_bump_percentage) / U256::from(100) + 1;
+            // the replacement is of the same type: EIP-1559 fees, or a legacy gas price
+            let (mut replacement, max_fee_per_gas): (TypedTransaction, U256) =
+                match tx.max_fee_per_gas {
+                    Some(max_fee_per_gas) => {
+                        let max_fee_per_gas = bump(max_fee_per_gas);
+                        let max_priority_fee_per_gas =
+                            bump(tx.max_priority_fee_per_gas.unwrap_or_default());
+                        let replacement = Eip1559TransactionRequest::new()
+                            .max_fee_per_gas(max_fee_per_gas)
+                            .max_priority_fee_per_gas(max_priority_fee_per_gas);
+                        (replacement.into(), max_fee_per_gas)
+                    }
+                    None => {
+                        let gas_price = bump(tx.gas_price.unwrap_or_default());
+                        (TransactionRequest::new().gas_price(gas_price).into(), gas_price)
+                    }
+                };
+
+            if let FeeStrategy::Eip1559 {
+                max_fee_per_gas_cap: Some(cap),
+                ..
+            } = &self.fee_strategy
+            {
+                if max_fee_per_gas > *cap {
+                    return Err(RegistrarError::Other(
+                        format!("Bumped max fee per gas ({max_fee_per_gas} wei) exceeds the cap ({cap} wei)").into(),
+                    ));
+                }
+            }
+
+            replacement.set_from(tx.from);
+            replacement.set_data(tx.input);
+            replacement.set_value(tx.value);
+            replacement.set_nonce(tx.nonce);
+            replacement.set_gas(tx.gas);
+            if let Some(to) = tx.to {
+                replacement.set_to(to);
+            }
+            if let Some(chain_id) = tx.chain_id {
+                replacement.set_chain_id(chain_id.as_u64());
+            }
+
+            let pending_tx = self
+                .signer
+                .send_transaction(replacement.clone(), None)
+                .await
+                .map_err(|e| RegistrarError::Other(e.to_string().into()))?;
+            telemetry::tx_hash(pending_tx.tx_hash());
+            self.nonce_manager
+                .record_sent(pending_tx.tx_hash(), replacement);
+
+            Ok(PendingResource {
+                tx_hash: pending_tx.tx_hash(),
+                nonce: tx.nonce,
+            })
+        })
+        .await
+    }
+
+    /// Estimate the gas and cost (in wei) of creating the resource, according to the
+    /// registrar's [FeeStrategy]. Fails if the write would revert.
+    pub async fn estimate_resource_cost(
+        &self,
+        did: &str,
+        resource_input: &ResourceInput,
+    ) -> Result<ResourceCostEstimate, RegistrarError> {
+        let signer = self.signer.default_sender();
+        let call = self
+            .create_resource_call(did, resource_input.clone())
+            .await?;
+
+        let gas = match call.estimate_gas().await {
+            Ok(gas) => gas,
+            Err(e) => {
+                let e = decode_contract_error(e);
+                return Err(self.decode_write_error(did, signer, e).await);
+            }
+        };
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self.estimate_fees().await?;
+
+        Ok(ResourceCostEstimate {
+            gas,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            max_cost_wei: gas * max_fee_per_gas,
+        })
+    }
+
+    /// Simulate creating the resource (via `eth_call`) without sending a transaction, to catch
+    /// reverts (e.g. [RegistrarError::Unauthorized]) before spending any funds.
+    pub async fn dry_run_create_resource(
+        &self,
+        did: &str,
+        resource_input: &ResourceInput,
+    ) -> Result<(), RegistrarError> {
+        let signer = self.signer.default_sender();
+        if let Some(signer) = signer {
+            self.ensure_authorized(did, signer, self.delegate_type.as_deref())
+                .await?;
+        }
+
+        let call = self
+            .create_resource_call(did, resource_input.clone())
+            .await?;
+        self.simulate_call(did, signer, &call).await
+    }
+
+    /// Submit a resource which was signed offline by the DID controller (see
+    /// [SignedResourceInput::sign]). In this mode the registrar's signer acts as a relayer,
+    /// submitting (and paying for) the transaction on behalf of the controller.
+    ///
+    /// Before sending, the signature is checked to be from the current controller of the DID,
+    /// failing with [RegistrarError::Unauthorized] if not.
+    pub async fn create_resource_signed(
+        &self,
+        signed_resource: SignedResourceInput,
+    ) -> Result<Resource, RegistrarError> {
+        let did = signed_resource.did.clone();
+        telemetry::write("create_resource_signed", Some(&did), async {
+            let signer = self
+                .registry
+                .recover_signed_resource_signer(&signed_resource)?;
+            self.ensure_authorized(&did, signer, None).await?;
+            self.validate_resource_input(&did, &signed_resource.resource, &[])
+                .await?;
+
+            let current_nonce = self.get_signed_write_nonce(signer).await?;
+            if current_nonce != signed_resource.nonce {
+                return Err(RegistrarError::Other(
+                    format!(
+                        "Resource was signed for nonce {}, but the current nonce is {current_nonce}",
+                        signed_resource.nonce
+                    )
+                    .into(),
+                ));
+            }
+
+            let call = self
+                .registry
+                .create_resource_signed_call(self.signer.clone(), signed_resource);
+            let pending_resource = self
+                .submit_create_resource_call(&did, Some(signer), call)
+                .await?;
+            self.await_written_resource(pending_resource.tx_hash).await
+        })
+        .await
+    }
+
+    /// Get the current nonce which the DID `controller` should sign their next
+    /// [SignedResourceInput] with.
+    pub async fn get_signed_write_nonce(&self, controller: H160) -> Result<U256, Box<dyn Error>> {
+        self.registry.get_signed_write_nonce(controller).await
+    }
+
+    /// [Self::submit_resource], recorded on the caller's `write` span
+    async fn submit_resource_input(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+    ) -> Result<PendingResource, RegistrarError> {
+        let signer = self.signer.default_sender();
+        if let Some(signer) = signer {
+            self.ensure_authorized(did, signer, self.delegate_type.as_deref())
+                .await?;
+        }
+
+        self.validate_resource_input(did, &resource_input, &[])
+            .await?;
+
+        let call = self.create_resource_call(did, resource_input).await?;
+        self.submit_create_resource_call(did, signer, call).await
+    }
+
+    /// [Self::await_resource], recorded on the caller's `write` span
+    async fn await_written_resource(&self, tx_hash: H256) -> Result<Resource, RegistrarError> {
+        self.await_written_resources(tx_hash)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                RegistrarError::Other(
+                    format!("Transaction {tx_hash:?} did not create a resource").into(),
+                )
+            })
+    }
+
+    /// [Self::await_resources], recorded on the caller's `write` span
+    async fn await_written_resources(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Vec<Resource>, RegistrarError> {
+        let mut tx_hash = tx_hash;
+        let receipt = loop {
+            telemetry::tx_hash(tx_hash);
+            let receipt = PendingTransaction::new(tx_hash, self.signer.provider())
+                .await
+                .map_err(|e| RegistrarError::Other(e.to_string().into()))?;
+            if let Some(receipt) = receipt {
+                self.nonce_manager.take_sent(tx_hash);
+                break receipt;
+            }
+
+            // dropped (the node no longer knows of it). A dropped transaction leaves a gap in the
+            // signer's nonces, blocking its later transactions, so it is re-sent if it was sent
+            // by this registrar, and its nonce was not used by another (e.g. replacement)
+            // transaction
+            tx_hash = self.resend_dropped_transaction(tx_hash).await?;
+        };
+        if let Some(gas_used) = receipt.gas_used {
+            telemetry::gas_used(gas_used);
+        }
+        if receipt.status == Some(U64::zero()) {
+            return Err(RegistrarError::Other(
+                format!("Transaction {tx_hash:?} was reverted").into(),
+            ));
+        }
+
+        let mut resources = vec![];
+        for event in decode_new_resource_events(receipt) {
+            let resource = self.resource_from_event(event).await;
+            resources.push(self.resolver.finalize_resource(resource).await?);
+        }
+
+        Ok(resources)
+    }
+
+    /// re-send the dropped transaction `tx_hash`, returning the hash it is re-sent as
+    async fn resend_dropped_transaction(&self, tx_hash: H256) -> Result<H256, RegistrarError> {
+        let dropped = || {
+            RegistrarError::Other(format!("Transaction {tx_hash:?} was dropped or replaced").into())
+        };
+        let tx = self.nonce_manager.take_sent(tx_hash).ok_or_else(dropped)?;
+        let (Some(sender), Some(nonce)) = (tx.from().copied(), tx.nonce().copied()) else {
+            return Err(dropped());
+        };
+        if self.is_nonce_pending(sender, nonce).await? {
+            return Err(dropped());
+        }
+
+        tracing::debug!(?tx_hash, %nonce, "re-sending dropped transaction");
+        let pending_tx = self
+            .signer
+            .send_transaction(tx.clone(), None)
+            .await
+            .map_err(|e| RegistrarError::Other(e.to_string().into()))?;
+        self.nonce_manager.record_sent(pending_tx.tx_hash(), tx);
+        Ok(pending_tx.tx_hash())
+    }
+
+    async fn create_resource_call(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+    ) -> Result<ContractCall<S, ()>, Box<dyn Error>> {
+        let resource_input = self.prepare_resource_input(resource_input).await?;
+        self.resource_call(did, resource_input)
+    }
+
+    /// the call to write an already prepared (see [Self::prepare_resource_input]) resource input
+    fn resource_call(
+        &self,
+        did: &str,
+        resource_input: ResourceInput,
+    ) -> Result<ContractCall<S, ()>, Box<dyn Error>> {
+        match &self.delegate_type {
+            Some(delegate_type) => self.registry.create_resource_as_delegate_call(
+                self.signer.clone(),
+                did,
+                delegate_type,
+                resource_input,
+            ),
+            None => {
+                Ok(self
+                    .registry
+                    .create_resource_call(self.signer.clone(), did, resource_input))
+            }
+        }
+    }
+
+    /// broadcast the resource creating `call` (written by `signer`) with the fee strategy applied.
+    async fn submit_create_resource_call(
+        &self,
+        did: &str,
+        signer: Option<H160>,
+        mut call: ContractCall<S, ()>,
+    ) -> Result<PendingResource, RegistrarError> {
+        self.apply_fee_strategy(&mut call).await?;
+
+        if self.dry_run_before_send {
+            self.simulate_call(did, signer, &call).await?;
+        }
+
+        let sender = self.signer.default_sender();
+        let mut allocated_nonce = None;
+        if let Some(sender) = sender {
+            let nonce = self
+                .nonce_manager
+                .next(self.signer.as_ref(), sender)
+                .await?;
+            call.tx.set_nonce(nonce);
+            allocated_nonce = Some(nonce);
+        }
+
+        // fill now (rather than on send), so that the nonce is known
+        if let Err(e) = self.signer.fill_transaction(&mut call.tx, None).await {
+            // not broadcast, so the allocated nonce is unused
+            if let (Some(sender), Some(nonce)) = (sender, allocated_nonce) {
+                self.release_nonce(sender, nonce).await;
+            }
+            let e = decode_contract_error(ContractError::<S>::from_middleware_error(e));
+            return Err(self.decode_write_error(did, signer, e).await);
+        }
+        let nonce = call.tx.nonce().copied().unwrap_or_default();
+
+        let pending_tx = match call.send().await {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => {
+                // a failed send (e.g. a timeout) may still have been broadcast, in which case
+                // the node knows of a transaction with the nonce
+                if let (Some(sender), Some(nonce)) = (sender, allocated_nonce) {
+                    if !self.is_nonce_pending(sender, nonce).await.unwrap_or(true) {
+                        self.release_nonce(sender, nonce).await;
+                    }
+                }
+                let e = decode_contract_error(e);
+                return Err(self.decode_write_error(did, signer, e).await);
+            }
+        };
+        telemetry::tx_hash(pending_tx.tx_hash());
+        self.nonce_manager
+            .record_sent(pending_tx.tx_hash(), call.tx.clone());
+
+        Ok(PendingResource {
+            tx_hash: pending_tx.tx_hash(),
+            nonce,
+        })
+    }
+
+    /// give back the unused `nonce` of the `sender`, or if a later nonce is already allocated,
+    /// fill the gap with a no-op transaction (a transfer of nothing to the sender), so that the
+    /// later transactions can be mined
+    async fn release_nonce(&self, sender: H160, nonce: U256) {
+        if self.nonce_manager.release(nonce) {
+            return;
+        }
+
+        let filler = TransactionRequest::new()
+            .from(sender)
+            .to(sender)
+            .value(0)
+            .nonce(nonce);
+        if let Err(e) = self.signer.send_transaction(filler, None).await {
+            tracing::warn!(%nonce, error = %e, "failed to fill nonce gap");
+        }
+    }
+
+    /// whether the node knows of a (mined or pending) transaction of the `sender` with the
+    /// `nonce`. Pending transactions are only counted up to the first gap in the nonces.
+    async fn is_nonce_pending(&self, sender: H160, nonce: U256) -> Result<bool, Box<dyn Error>> {
+        let pending_count = self
+            .signer
+            .get_transaction_count(sender, Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        Ok(pending_count > nonce)
+    }
+
+    async fn simulate_call(
+        &self,
+        did: &str,
+        signer: Option<H160>,
+        call: &ContractCall<S, ()>,
+    ) -> Result<(), RegistrarError> {
+        match call.call().await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let e = decode_contract_error(e);
+                Err(self.decode_write_error(did, signer, e).await)
+            }
+        }
+    }
+
+    /// set the EIP-1559 fees of the `call` according to the fee strategy.
+    async fn apply_fee_strategy(
+        &self,
+        call: &mut ContractCall<S, ()>,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.fee_strategy == FeeStrategy::ProviderDefault {
+            return Ok(());
+        }
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) = self.estimate_fees().await?;
+        let TypedTransaction::Eip1559(tx) = &mut call.tx else {
+            return Err("EIP-1559 fee strategy requires an EIP-1559 transaction".into());
+        };
+        tx.max_fee_per_gas = Some(max_fee_per_gas);
+        tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+
+        Ok(())
+    }
+
+    /// the (`maxFeePerGas`, `maxPriorityFeePerGas`) for a transaction sent now, according to the
+    /// fee strategy.
+    async fn estimate_fees(&self) -> Result<(U256, U256), Box<dyn Error>> {
+        let (estimated_max_fee, estimated_priority_fee) = self
+            .signer
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+
+        let FeeStrategy::Eip1559 {
+            max_fee_per_gas_cap,
+            priority_fee,
+        } = &self.fee_strategy
+        else {
+            return Ok((estimated_max_fee, estimated_priority_fee));
+        };
+
+        let mut max_priority_fee = match priority_fee {
+            PriorityFeePolicy::Estimated => estimated_priority_fee,
+            PriorityFeePolicy::Fixed(fee) => *fee,
+            PriorityFeePolicy::Percentage(percentage) => {
+                estimated_priority_fee * U256::from(*percentage) / U256::from(100)
+            }
+        };
+        // keep the base fee headroom of the estimate, but with the chosen priority fee
+        let mut max_fee =
+            estimated_max_fee.saturating_sub(estimated_priority_fee) + max_priority_fee;
+
+        if let Some(cap) = max_fee_per_gas_cap {
+            let base_fee = self
+                .signer
+                .get_block(BlockNumber::Latest)
+                .await
+                .map_err(|e| anyhow!(e.to_string()))?
+                .and_then(|block| block.base_fee_per_gas)
+                .unwrap_or_default();
+            if base_fee > *cap {
+                return Err(format!(
+                    "Current base fee ({base_fee} wei) exceeds the max fee per gas cap ({cap} wei)"
+                )
+                .into());
+            }
+
+            max_fee = max_fee.min(*cap);
+            max_priority_fee = max_priority_fee.min(*cap);
+        }
+
+        Ok((max_fee, max_priority_fee))
+    }
+
+    /// check that `signer` can currently write resources for the `did`, as the DID controller,
+    /// or as a delegate of `delegate_type` (if some).
+    async fn ensure_authorized(
+        &self,
+        did: &str,
+        signer: H160,
+        delegate_type: Option<&str>,
+    ) -> Result<(), RegistrarError> {
+        let current_controller = self.registry.get_did_controller(did).await?;
+        if signer == current_controller {
+            return Ok(());
+        }
+
+        if let Some(delegate_type) = delegate_type {
+            if self
+                .registry
+                .is_valid_did_delegate(did, delegate_type, signer)
+                .await?
+            {
+                return Ok(());
+            }
+        }
+
+        Err(RegistrarError::Unauthorized {
+            did: did.to_owned(),
+            signer: Some(signer),
+            current_controller,
+        })
+    }
+
+    /// decode authorization reverts from the registry into [RegistrarError::Unauthorized]
+    /// (e.g. if the DID controller changed after the pre-flight check).
+    async fn decode_write_error(
+        &self,
+        did: &str,
+        signer: Option<H160>,
+        e: Box<dyn Error>,
+    ) -> RegistrarError {
+        let is_unauthorized = e
+            .downcast_ref::<RegistryRevert>()
+            .is_some_and(RegistryRevert::is_unauthorized);
+        if !is_unauthorized {
+            return RegistrarError::Other(e);
+        }
+
+        match self.registry.get_did_controller(did).await {
+            Ok(current_controller) => RegistrarError::Unauthorized {
+                did: did.to_owned(),
+                signer,
+                current_controller,
+            },
+            Err(_) => RegistrarError::Other(e),
+        }
+    }
+
+    /// check the `resource_input` against the existing versions of the resource (and the
+    /// `batch_inputs` written before it in the same batch): its name & type must not be ambiguous
+    /// with those of another resource (for version 1 registries), and its version id must be
+    /// permitted by the version policy
+    async fn validate_resource_input(
+        &self,
+        did: &str,
+        resource_input: &ResourceInput,
+        batch_inputs: &[ResourceInput],
+    ) -> Result<(), RegistrarError> {
+        // the existing versions are slow to fetch (scanning the registry's logs), only do so if
+        // they are checked
+        let is_v1_registry = self.registry.registry_version().await? == RegistryVersion::V1;
+        if !is_v1_registry && self.version_policy == VersionPolicy::Any {
+            return Ok(());
+        }
+
+        let existing_versions = self
             .registry
-            .create_or_update_resource(self.signer.clone(), &did, resource_input)
+            .get_resource_versions(
+                did,
+                &resource_input.resource_name,
+                &resource_input.resource_type,
+            )
+            .await?;
+
+        // version 1 registries key resources by their concatenated name & type, so the versions
+        // found are of any resource whose name & type concatenate the same way
+        if is_v1_registry {
+            let existing_names_and_types = existing_versions.iter().map(|event| {
+                (
+                    event.resource.metadata.resource_name.as_str(),
+                    event.resource.metadata.resource_type.as_str(),
+                )
+            });
+            let batch_names_and_types = batch_inputs
+                .iter()
+                .map(|input| (input.resource_name.as_str(), input.resource_type.as_str()));
+            for (name, resource_type) in existing_names_and_types.chain(batch_names_and_types) {
+                if resource_input.is_ambiguous_with(name, resource_type) {
+                    return Err(ResourceInputError::AmbiguousNameAndType {
+                        existing_name: name.to_owned(),
+                        existing_type: resource_type.to_owned(),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        if self.version_policy == VersionPolicy::Any {
+            return Ok(());
+        }
+
+        let batch_versions = batch_inputs.iter().filter(|input| {
+            input.resource_name == resource_input.resource_name
+                && input.resource_type == resource_input.resource_type
+        });
+        let versions: Vec<&str> = existing_versions
+            .iter()
+            .filter(|event| event.resource.metadata.media_type != TOMBSTONE_MEDIA_TYPE)
+            .map(|event| event.resource.metadata.resource_version.as_str())
+            .chain(batch_versions.map(|input| input.resource_version_id.as_str()))
+            .collect();
+
+        let version = resource_input.resource_version_id.as_str();
+        match self.version_policy {
+            VersionPolicy::Any => Ok(()),
+            VersionPolicy::Unique => {
+                if !version.is_empty() && versions.contains(&version) {
+                    return Err(RegistrarError::InvalidVersion(format!(
+                        "Version {version} already exists"
+                    )));
+                }
+                Ok(())
+            }
+            VersionPolicy::Semver => {
+                let new_version = Version::parse(version).map_err(|e| {
+                    RegistrarError::InvalidVersion(format!("{version:?} is not semver: {e}"))
+                })?;
+                let highest_version = versions
+                    .iter()
+                    .filter_map(|version| Version::parse(version).ok())
+                    .max();
+                match highest_version {
+                    Some(highest_version) if new_version <= highest_version => {
+                        Err(RegistrarError::InvalidVersion(format!(
+                            "Version {new_version} must be greater than the previous version {highest_version}"
+                        )))
+                    }
+                    _ => Ok(()),
+                }
+            }
+        }
+    }
+
+    /// the latest version of the resource, if it is identical to the `resource_input` (same
+    /// content, media type and version id)
+    async fn find_unchanged_resource(
+        &self,
+        did: &str,
+        resource_input: &ResourceInput,
+    ) -> Result<Option<Resource>, Box<dyn Error>> {
+        let latest = self
+            .resolver
+            .resolve_latest_resource(
+                did,
+                &resource_input.resource_name,
+                &resource_input.resource_type,
+            )
             .await?;
 
+        Ok(latest.filter(|latest| {
+            latest.content == resource_input.content
+                && latest.metadata.media_type == resource_input.media_type
+                && latest
+                    .metadata
+                    .resource_version_id
+                    .as_deref()
+                    .unwrap_or_default()
+                    == resource_input.resource_version_id
+        }))
+    }
+
+    /// the input to write to the ledger: content encoded, then (if a content store is configured)
+    /// stored off-chain, leaving a reference to the content
+    async fn prepare_resource_input(
+        &self,
+        resource_input: ResourceInput,
+    ) -> Result<ResourceInput, Box<dyn Error>> {
+        let resource_input = encode_resource_input(resource_input)?;
+        match &self.content_store {
+            Some(store) => content_store::offload_content(store.as_ref(), resource_input).await,
+            None => Ok(resource_input),
+        }
+    }
+
+    async fn resource_from_event(&self, resource: NewResourceFilter) -> Resource {
         let metadata_node = self
             .resolver
             .resolve_metadata_chain_node_for_event(&resource)
             .await;
 
-        Ok(Resource::from((
-            resource,
-            metadata_node,
-            ChainId(self.chain_id),
-        )))
+        Resource::from((resource, metadata_node, ChainId(self.chain_id)))
     }
 }
@@ -1,24 +1,70 @@
-use std::error::Error;
+use std::{error::Error, sync::Arc};
 
 use chrono::Utc;
+use ethers::providers::Middleware;
 #[cfg(feature = "thegraph")]
 use ethers::types::U256;
+use semver::Version;
 
 use crate::{
+    chunking::{assemble_chunks, ChunkManifest, CHUNK_MANIFEST_MEDIA_TYPE, MAX_CHUNKS},
     config::ContractNetworkConfig,
+    content_encoding::decode_resource,
+    content_store::{self, ContentStore},
     contracts::ethr_dlr_registry::{
         EthrDIDLinkedResourcesRegistry, NewResourceFilter, ResourceVersionMetadataChainNode,
+        TOMBSTONE_MEDIA_TYPE,
     },
+    telemetry::{self, HttpProvider},
     types::{output::Resource, query::ResourceQuery},
-    utils::{did_identity_as_full_did, ChainId},
+    utils::{
+        did_identity_as_full_did, extract_did_of_dlr_resource_uri, is_network_of_chain_id, ChainId,
+    },
 };
 
 #[cfg(feature = "thegraph")]
 use crate::subgraph::{self};
 
-pub struct EthrDidLinkedResourcesResolver {
-    registry: EthrDIDLinkedResourcesRegistry,
+/// Options for resolving resources, see
+/// [EthrDidLinkedResourcesResolver::resolve_query_with_options].
+#[derive(Clone, Debug, Default)]
+pub struct ResolutionOptions {
+    /// When resolving a deactivated resource by name & type, return the last version before it
+    /// was deactivated (with [crate::types::output::ResourceMetadata::deactivated] set), rather
+    /// than failing.
+    pub include_deactivated: bool,
+    /// Which version of a resource to resolve when resolving by name & type.
+    pub version_selection: VersionSelection,
+    /// Audit who controlled the DID when the resource was written, setting
+    /// [crate::types::output::ResourceMetadata::controller_at_creation]. Costs extra RPC calls
+    /// (including a scan of the DID's ownership history).
+    pub audit_controller: bool,
+}
+
+/// How the version of a resource is selected when resolving by name & type (at a version time).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum VersionSelection {
+    /// The most recently created version.
+    #[default]
+    LatestByTime,
+    /// The version with the highest semver `resourceVersionId`. Versions whose id is not semver
+    /// are ignored.
+    ///
+    /// The versions are found by scanning the registry's logs from genesis (`eth_getLogs`), even
+    /// with the `thegraph` feature, which may be slow (or exceed the node's log range limits).
+    HighestSemver,
+}
+
+/// how resources are looked up by name & type (recorded on `resolve` spans, see [telemetry])
+#[cfg(feature = "thegraph")]
+const RESOLUTION_BACKEND: &str = "thegraph";
+#[cfg(not(feature = "thegraph"))]
+const RESOLUTION_BACKEND: &str = "rpc";
+
+pub struct EthrDidLinkedResourcesResolver<M = HttpProvider> {
+    registry: EthrDIDLinkedResourcesRegistry<M>,
     chain_id: u64,
+    content_stores: Vec<Arc<dyn ContentStore>>,
 }
 
 impl EthrDidLinkedResourcesResolver {
@@ -26,59 +72,281 @@ impl EthrDidLinkedResourcesResolver {
         Self {
             chain_id: config.chain_id,
             registry: EthrDIDLinkedResourcesRegistry::new(config),
+            content_stores: vec![],
+        }
+    }
+}
+
+impl<M: Middleware + 'static> EthrDidLinkedResourcesResolver<M> {
+    /// Resolve through the given `client` rather than a new provider for the `config.rpc_url`,
+    /// e.g. to share a provider (and its middleware) with other readers.
+    pub fn with_client(config: ContractNetworkConfig, client: Arc<M>) -> Self {
+        Self {
+            chain_id: config.chain_id,
+            registry: EthrDIDLinkedResourcesRegistry::with_client(config, client),
+            content_stores: vec![],
         }
     }
 
+    /// Add a store to fetch the content of resources from, for resources whose content is stored
+    /// off-chain (see [crate::content_store]). Fetched content is verified against the on-chain
+    /// content hash.
+    pub fn add_content_store(&mut self, content_store: Arc<dyn ContentStore>) {
+        self.content_stores.push(content_store);
+    }
+
     /// TODO
     ///
     /// Resolve an exact resource with a DLR query
     pub async fn resolve_query(&self, query: &str) -> Result<Resource, Box<dyn Error>> {
-        let query = ResourceQuery::parse_from_str(query)?;
-        let did_id = query.did_identity;
-        let did = did_identity_as_full_did(&did_id, self.chain_id);
-        let params = query.parameters;
-
-        if let Some(resource_id) = params.resource_id {
-            let resource = self.registry.get_resource_by_id(&did, &resource_id).await;
-            let Some(resource) = resource else {
-                return Err("Not Found".into());
+        self.resolve_query_with_options(query, &ResolutionOptions::default())
+            .await
+    }
+
+    /// Like [Self::resolve_query], with [ResolutionOptions].
+    ///
+    /// Resolving a deactivated resource by name & type (i.e. a version time at or after its
+    /// tombstone) fails, unless [ResolutionOptions::include_deactivated] is set.
+    pub async fn resolve_query_with_options(
+        &self,
+        query: &str,
+        options: &ResolutionOptions,
+    ) -> Result<Resource, Box<dyn Error>> {
+        telemetry::resolve(query, RESOLUTION_BACKEND, async {
+            let query = ResourceQuery::parse_from_str(query)?;
+            if !is_network_of_chain_id(query.network.as_deref(), self.chain_id) {
+                return Err(format!(
+                    "DID network {} is not the resolver's network (chain ID {})",
+                    query.network.as_deref().unwrap_or("mainnet"),
+                    self.chain_id
+                )
+                .into());
+            }
+            let did_id = query.did_identity;
+            let did = did_identity_as_full_did(&did_id, self.chain_id);
+            let params = query.parameters;
+
+            if let Some(resource_id) = params.resource_id {
+                let resource = self.resolve_resource_by_id(&did, &resource_id).await?;
+                let resource = self.finalize_resource(resource).await?;
+                return self.audit_resource(resource, options).await;
+            }
+
+            if params.all_resource_versions.is_some()
+                || params.latest_resource_version.is_some()
+                || params.linked_resource.is_some()
+                || params.resource_metadata.is_some()
+                || params.resource_version_id.is_some()
+                || params.service.is_some()
+            {
+                // probably can't support indexing on these params (without thegraph or scanning)
+                return Err("Unsupported param".into());
+            }
+
+            let version_time = params.version_time.unwrap_or_else(|| Utc::now());
+
+            let (Some(resource_name), Some(resource_type)) =
+                (params.resource_name, params.resource_type)
+            else {
+                // other queries are not supported for now..
+                return Err("Not found - too vague".into());
             };
-            let metadata_node = self.resolve_metadata_chain_node_for_event(&resource).await;
-            return Ok(Resource::from((
-                resource,
-                metadata_node,
-                ChainId(self.chain_id),
-            )));
-        }
 
-        if params.all_resource_versions.is_some()
-            || params.latest_resource_version.is_some()
-            || params.linked_resource.is_some()
-            || params.resource_metadata.is_some()
-            || params.resource_version_id.is_some()
-        {
-            // probably can't support indexing on these params (without thegraph or scanning)
-            return Err("Unsupported param".into());
+            // resolve as a resource (known by name+type) at an epoch
+            let mut resource = self
+                .resolve_resource_by_name_and_type_at_epoch(
+                    &did,
+                    &resource_name,
+                    &resource_type,
+                    version_time.timestamp() as i64,
+                )
+                .await?
+                .ok_or("Not found")?;
+
+            // the latest version at the version time is a tombstone
+            let deactivated = resource.metadata.media_type == TOMBSTONE_MEDIA_TYPE;
+            if deactivated {
+                if !options.include_deactivated {
+                    return Err(
+                        format!("Resource {resource_name} {resource_type} is deactivated").into(),
+                    );
+                }
+                // the last version before deactivation
+                let Some(previous_version_id) = &resource.metadata.previous_version_id else {
+                    return Err("Not found".into());
+                };
+                resource = self
+                    .resolve_resource_by_id(&did, previous_version_id)
+                    .await?;
+            }
+
+            if options.version_selection == VersionSelection::HighestSemver {
+                resource = self
+                    .resolve_highest_semver_resource(
+                        &did,
+                        &resource_name,
+                        &resource_type,
+                        version_time.timestamp() as u64,
+                    )
+                    .await?;
+            }
+
+            let mut resource = self.finalize_resource(resource).await?;
+            resource.metadata.deactivated = deactivated;
+            self.audit_resource(resource, options).await
+        })
+        .await
+    }
+
+    /// apply the audits requested in the `options` to the `resource`
+    async fn audit_resource(
+        &self,
+        mut resource: Resource,
+        options: &ResolutionOptions,
+    ) -> Result<Resource, Box<dyn Error>> {
+        if !options.audit_controller {
+            return Ok(resource);
         }
 
-        let version_time = params.version_time.unwrap_or_else(|| Utc::now());
+        let did = extract_did_of_dlr_resource_uri(&resource.metadata.resource_uri);
+        let resource_id = resource
+            .metadata
+            .resource_id
+            .as_deref()
+            .ok_or("Resource has no ID")?;
+        resource.metadata.controller_at_creation = Some(
+            self.registry
+                .get_controller_at_creation(&did, resource_id)
+                .await?,
+        );
+        Ok(resource)
+    }
+
+    /// the version of the resource (by name & type) created at or before `epoch` with the highest
+    /// semver version id, excluding tombstones
+    async fn resolve_highest_semver_resource(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+        epoch: u64,
+    ) -> Result<Resource, Box<dyn Error>> {
+        let versions = self
+            .registry
+            .get_resource_versions(did, resource_name, resource_type)
+            .await?;
 
-        let (Some(resource_name), Some(resource_type)) =
-            (params.resource_name, params.resource_type)
+        let highest = versions
+            .into_iter()
+            .filter(|event| {
+                event.resource.metadata.created.block_timestamp <= epoch
+                    && event.resource.metadata.media_type != TOMBSTONE_MEDIA_TYPE
+            })
+            .filter_map(|event| {
+                let version = Version::parse(&event.resource.metadata.resource_version).ok()?;
+                Some((version, event))
+            })
+            .max_by(|(a, _), (b, _)| a.cmp(b));
+
+        let Some((_, event)) = highest else {
+            return Err(
+                format!("Resource {resource_name} {resource_type} has no semver versions").into(),
+            );
+        };
+
+        let metadata_node = self.resolve_metadata_chain_node_for_event(&event).await;
+        Ok(Resource::from((
+            event,
+            metadata_node,
+            ChainId(self.chain_id),
+        )))
+    }
+
+    /// the latest version of the resource (by name & type) on the ledger, if any. Which may be a
+    /// tombstone.
+    pub(super) async fn resolve_latest_resource(
+        &self,
+        did: &str,
+        resource_name: &str,
+        resource_type: &str,
+    ) -> Result<Option<Resource>, Box<dyn Error>> {
+        let Some((resource, metadata_node)) = self
+            .registry
+            .get_latest_resource_by_name_and_type(did, resource_name, resource_type)
+            .await?
         else {
-            // other queries are not supported for now..
-            return Err("Not found - too vague".into());
+            return Ok(None);
         };
 
-        // resolve as a resource (known by name+type) at an epoch
-        self.resolve_resource_by_name_and_type_at_epoch(
-            &did,
-            &resource_name,
-            &resource_type,
-            version_time.timestamp() as i64,
-        )
-        .await
-        .ok_or("Not found".into())
+        let resource = Resource::from((resource, metadata_node, ChainId(self.chain_id)));
+        Ok(Some(self.finalize_resource(resource).await?))
+    }
+
+    async fn resolve_resource_by_id(
+        &self,
+        did: &str,
+        resource_id: &str,
+    ) -> Result<Resource, Box<dyn Error>> {
+        let resource = self.registry.get_resource_by_id(did, resource_id).await?;
+        let Some(resource) = resource else {
+            return Err("Not Found".into());
+        };
+        let metadata_node = self.resolve_metadata_chain_node_for_event(&resource).await;
+        Ok(Resource::from((
+            resource,
+            metadata_node,
+            ChainId(self.chain_id),
+        )))
+    }
+
+    /// complete a resource read from the ledger: its original content (fetched and verified if
+    /// stored off-chain, decoded if encoded), and whether it is deactivated as of its own version
+    /// (i.e. whether it is a tombstone)
+    pub(super) async fn finalize_resource(
+        &self,
+        resource: Resource,
+    ) -> Result<Resource, Box<dyn Error>> {
+        let deactivated = resource.metadata.media_type == TOMBSTONE_MEDIA_TYPE;
+        let resource = self.assemble_chunked_content(resource).await?;
+        let resource = content_store::dereference_content(&self.content_stores, resource).await?;
+        let mut resource = decode_resource(resource)?;
+        resource.metadata.deactivated = deactivated;
+
+        Ok(resource)
+    }
+
+    /// if the `resource` is a chunk manifest, fetch its chunks and assemble the content
+    async fn assemble_chunked_content(
+        &self,
+        mut resource: Resource,
+    ) -> Result<Resource, Box<dyn Error>> {
+        if resource.metadata.media_type != CHUNK_MANIFEST_MEDIA_TYPE {
+            return Ok(resource);
+        }
+
+        let manifest: ChunkManifest = serde_json::from_slice(&resource.content)?;
+        if manifest.chunk_ids.len() > MAX_CHUNKS {
+            return Err(format!(
+                "Chunk manifest has {} chunks, more than the maximum of {MAX_CHUNKS}",
+                manifest.chunk_ids.len()
+            )
+            .into());
+        }
+        let did = extract_did_of_dlr_resource_uri(&resource.metadata.resource_uri);
+
+        let mut chunks = vec![];
+        for chunk_id in &manifest.chunk_ids {
+            let chunk = self
+                .registry
+                .get_resource_by_id(&did, chunk_id)
+                .await?
+                .ok_or_else(|| format!("Chunk {chunk_id} of {did} not found"))?;
+            chunks.push(chunk.resource.content.to_vec());
+        }
+
+        resource.content = assemble_chunks(&manifest, chunks)?;
+        resource.metadata.media_type = manifest.media_type;
+        Ok(resource)
     }
 
     #[allow(unreachable_code)]
@@ -88,7 +356,7 @@ impl EthrDidLinkedResourcesResolver {
         resource_name: &str,
         resource_type: &str,
         epoch: i64,
-    ) -> Option<Resource> {
+    ) -> Result<Option<Resource>, Box<dyn Error>> {
         #[cfg(feature = "thegraph")]
         return self
             .resolve_resource_by_name_and_type_at_epoch_via_subgraph(
@@ -115,16 +383,19 @@ impl EthrDidLinkedResourcesResolver {
         resource_name: &str,
         resource_type: &str,
         epoch: i64,
-    ) -> Option<Resource> {
-        let (resource, metadata_node) = self
+    ) -> Result<Option<Resource>, Box<dyn Error>> {
+        let Some((resource, metadata_node)) = self
             .registry
             .get_resource_by_name_and_type_at_epoch(did, resource_name, resource_type, epoch as u64)
-            .await?;
-        Some(Resource::from((
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Resource::from((
             resource,
             metadata_node,
             ChainId(self.chain_id),
-        )))
+        ))))
     }
 
     #[cfg(feature = "thegraph")]
@@ -134,7 +405,7 @@ impl EthrDidLinkedResourcesResolver {
         resource_name: &str,
         resource_type: &str,
         epoch: i64,
-    ) -> Option<Resource> {
+    ) -> Result<Option<Resource>, Box<dyn Error>> {
         let graph_resource = subgraph::query::get_resource_event_most_recent_to(
             did,
             resource_name,
@@ -144,7 +415,7 @@ impl EthrDidLinkedResourcesResolver {
         .await;
 
         let Some(graph_resource) = graph_resource else {
-            return None;
+            return Ok(None);
         };
 
         let metadata_node = self
@@ -159,11 +430,11 @@ impl EthrDidLinkedResourcesResolver {
             )
             .await;
 
-        Some(Resource::from((
+        Ok(Some(Resource::from((
             graph_resource,
             metadata_node,
             ChainId(self.chain_id),
-        )))
+        ))))
     }
 
     pub(super) async fn resolve_metadata_chain_node_for_event(
@@ -183,6 +454,10 @@ impl EthrDidLinkedResourcesResolver {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use ethers::providers::{Http, Provider};
+
     use crate::{
         contracts::{
             ethr_dlr_registry::EthrDIDLinkedResourcesRegistry,
@@ -216,6 +491,7 @@ mod tests {
                     resource_version_id: String::new(),
                     media_type: String::from("text/plain"),
                     content: "hello world".as_bytes().to_vec(),
+                    content_encoding: None,
                 },
             )
             .await
@@ -240,4 +516,50 @@ mod tests {
         let resolved_res = resolver.resolve_query(&resource_query).await.unwrap();
         dbg!(resolved_res);
     }
+
+    #[tokio::test]
+    async fn test_resolve_with_shared_client() {
+        let conf = TestConfig::load();
+
+        let provider = Arc::new(Provider::<Http>::try_from(&conf.rpc_url).unwrap());
+        let registry = EthrDIDLinkedResourcesRegistry::with_client(
+            conf.get_dlr_network_config(),
+            provider.clone(),
+        );
+        let resolver = super::EthrDidLinkedResourcesResolver::with_client(
+            conf.get_dlr_network_config(),
+            provider.clone(),
+        );
+
+        let signer = get_writer_ethers_client(0, &conf);
+        let did = did_identity_as_full_did(&signer.address(), conf.chain_id);
+        let resource_name = format!("foo{}", uuid::Uuid::new_v4());
+
+        let created_resource = registry
+            .create_or_update_resource(
+                signer.clone(),
+                &did,
+                ResourceInput::builder()
+                    .resource_name(&resource_name)
+                    .resource_type("bar")
+                    .media_type("text/plain")
+                    .content("hello world")
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let resolved_resource = resolver
+            .resolve_query(&format!(
+                "{did}/resources/{resource_id}",
+                resource_id = created_resource.resource_id
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resolved_resource.content, b"hello world");
+
+        // one provider for all reads
+        assert_eq!(Arc::strong_count(&provider), 3);
+    }
 }
@@ -0,0 +1,374 @@
+//! Decoding of resource content by media type (or resource type).
+//!
+//! [Resource::decode] deserializes JSON (or, with the `cbor` feature, CBOR) content into a typed
+//! value, and [Resource::text] reads text content. [ContentDecoders] is a registry of decoders
+//! into [ResourceContent], keyed by media type, with custom decoders for resource types:
+//!
+//! ```ignore
+//! let mut decoders = ContentDecoders::default();
+//! decoders.register_resource_type(STATUS_LIST_RESOURCE_TYPE, |content| {
+//!     Ok(ResourceContent::custom(StatusListUpdateLedgerData::from_ledger_bytes(content)))
+//! });
+//!
+//! match resource.decode_content(&decoders)? {
+//!     ResourceContent::Json(value) => ..,
+//!     ResourceContent::Custom(custom) => custom.downcast_ref::<StatusListUpdateLedgerData>(),
+//!     ..
+//! }
+//! ```
+
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use mime::Mime;
+use serde::de::DeserializeOwned;
+
+use crate::{error::ContentDecodeError, types::output::Resource};
+
+pub const JSON_MEDIA_TYPE: &str = "application/json";
+pub const TEXT_MEDIA_TYPE: &str = "text/plain";
+#[cfg(feature = "cbor")]
+pub const CBOR_MEDIA_TYPE: &str = "application/cbor";
+
+/// The content of a resource, decoded by a [ContentDecoders].
+#[derive(Clone)]
+pub enum ResourceContent {
+    Json(serde_json::Value),
+    Text(String),
+    #[cfg(feature = "cbor")]
+    Cbor(ciborium::Value),
+    /// Decoded by a custom decoder, see [ResourceContent::custom].
+    Custom(Arc<dyn Any + Send + Sync>),
+    /// Content of a media type without a decoder, as is.
+    Bytes(Vec<u8>),
+}
+
+impl ResourceContent {
+    pub fn custom<T: Any + Send + Sync>(value: T) -> Self {
+        ResourceContent::Custom(Arc::new(value))
+    }
+
+    /// The custom decoded value, if it is a `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        match self {
+            ResourceContent::Custom(value) => value.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for ResourceContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceContent::Json(value) => f.debug_tuple("Json").field(value).finish(),
+            ResourceContent::Text(text) => f.debug_tuple("Text").field(text).finish(),
+            #[cfg(feature = "cbor")]
+            ResourceContent::Cbor(value) => f.debug_tuple("Cbor").field(value).finish(),
+            ResourceContent::Custom(_) => f.write_str("Custom(..)"),
+            ResourceContent::Bytes(bytes) => f.debug_tuple("Bytes").field(bytes).finish(),
+        }
+    }
+}
+
+/// A decoder of resource content.
+pub type ContentDecoder =
+    Arc<dyn Fn(&[u8]) -> Result<ResourceContent, ContentDecodeError> + Send + Sync>;
+
+/// Registry of [ContentDecoder]s, by media type (e.g. `application/json`, without parameters)
+/// and by resource type. Decoders of the resource type take precedence.
+///
+/// The default registry decodes JSON (including `+json` media types, e.g. `application/ld+json`),
+/// `text/*` and, with the `cbor` feature, CBOR (including `+cbor`). Content of other media types
+/// is left as [ResourceContent::Bytes].
+#[derive(Clone)]
+pub struct ContentDecoders {
+    by_media_type: HashMap<String, ContentDecoder>,
+    by_resource_type: HashMap<String, ContentDecoder>,
+}
+
+impl Default for ContentDecoders {
+    fn default() -> Self {
+        let mut decoders = Self::empty();
+        decoders.register_media_type(JSON_MEDIA_TYPE, |content| {
+            Ok(ResourceContent::Json(decode_json(content)?))
+        });
+        decoders.register_media_type(TEXT_MEDIA_TYPE, |content| {
+            Ok(ResourceContent::Text(decode_text(content)?))
+        });
+        #[cfg(feature = "cbor")]
+        decoders.register_media_type(CBOR_MEDIA_TYPE, |content| {
+            Ok(ResourceContent::Cbor(decode_cbor(content)?))
+        });
+        decoders
+    }
+}
+
+impl ContentDecoders {
+    /// A registry without any decoders.
+    pub fn empty() -> Self {
+        Self {
+            by_media_type: HashMap::new(),
+            by_resource_type: HashMap::new(),
+        }
+    }
+
+    /// Decode content of the `media_type` (e.g. `application/json`) with the `decoder`.
+    pub fn register_media_type(
+        &mut self,
+        media_type: &str,
+        decoder: impl Fn(&[u8]) -> Result<ResourceContent, ContentDecodeError> + Send + Sync + 'static,
+    ) {
+        self.by_media_type
+            .insert(media_type.to_ascii_lowercase(), Arc::new(decoder));
+    }
+
+    /// Decode content of resources of the `resource_type` (e.g. `anonCredsStatusList`) with the
+    /// `decoder`, whatever their media type.
+    pub fn register_resource_type(
+        &mut self,
+        resource_type: &str,
+        decoder: impl Fn(&[u8]) -> Result<ResourceContent, ContentDecodeError> + Send + Sync + 'static,
+    ) {
+        self.by_resource_type
+            .insert(resource_type.to_owned(), Arc::new(decoder));
+    }
+
+    pub fn decode(&self, resource: &Resource) -> Result<ResourceContent, ContentDecodeError> {
+        if let Some(decoder) = self.by_resource_type.get(&resource.metadata.resource_type) {
+            return decoder(&resource.content);
+        }
+
+        let mime = resource_mime(resource)?;
+        let decoder = self
+            .by_media_type
+            .get(mime.essence_str())
+            .or_else(|| {
+                // structured syntax suffixes, e.g. application/ld+json
+                let suffix = mime.suffix()?;
+                let media_type = format!("application/{}", suffix.as_str());
+                self.by_media_type.get(&media_type)
+            })
+            .or_else(|| {
+                let media_type = format!("{}/plain", mime.type_());
+                self.by_media_type.get(&media_type)
+            });
+
+        match decoder {
+            Some(decoder) => decoder(&resource.content),
+            None => Ok(ResourceContent::Bytes(resource.content.clone())),
+        }
+    }
+}
+
+impl Resource {
+    /// Decode the content with the `decoders`.
+    pub fn decode_content(
+        &self,
+        decoders: &ContentDecoders,
+    ) -> Result<ResourceContent, ContentDecodeError> {
+        decoders.decode(self)
+    }
+
+    /// Deserialize JSON (or, with the `cbor` feature, CBOR) content into a `T`, failing if the
+    /// resource is of another media type.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, ContentDecodeError> {
+        let mime = resource_mime(self)?;
+        if is_media_type(&mime, "json") {
+            return decode_json(&self.content);
+        }
+        #[cfg(feature = "cbor")]
+        if is_media_type(&mime, "cbor") {
+            return decode_cbor(&self.content);
+        }
+
+        #[cfg(not(feature = "cbor"))]
+        let expected = JSON_MEDIA_TYPE;
+        #[cfg(feature = "cbor")]
+        let expected = "application/json or application/cbor";
+        Err(ContentDecodeError::MediaTypeMismatch {
+            expected: expected.to_owned(),
+            media_type: self.metadata.media_type.clone(),
+        })
+    }
+
+    /// The content of a `text/*` resource, failing if the resource is of another media type.
+    pub fn text(&self) -> Result<String, ContentDecodeError> {
+        if resource_mime(self)?.type_() != mime::TEXT {
+            return Err(ContentDecodeError::MediaTypeMismatch {
+                expected: String::from("text/*"),
+                media_type: self.metadata.media_type.clone(),
+            });
+        }
+        decode_text(&self.content)
+    }
+}
+
+fn resource_mime(resource: &Resource) -> Result<Mime, ContentDecodeError> {
+    let media_type = &resource.metadata.media_type;
+    media_type
+        .parse()
+        .map_err(|e| ContentDecodeError::InvalidMediaType(format!("{media_type:?}: {e}")))
+}
+
+/// whether the `mime` is `application/<name>` or has the `+<name>` suffix
+fn is_media_type(mime: &Mime, name: &str) -> bool {
+    let suffix = mime.suffix().map(|suffix| suffix.as_str());
+    (mime.type_() == mime::APPLICATION && mime.subtype() == name) || suffix == Some(name)
+}
+
+fn decode_json<T: DeserializeOwned>(content: &[u8]) -> Result<T, ContentDecodeError> {
+    serde_json::from_slice(content).map_err(|e| ContentDecodeError::Malformed(e.to_string()))
+}
+
+fn decode_text(content: &[u8]) -> Result<String, ContentDecodeError> {
+    String::from_utf8(content.to_vec()).map_err(|e| ContentDecodeError::Malformed(e.to_string()))
+}
+
+#[cfg(feature = "cbor")]
+fn decode_cbor<T: DeserializeOwned>(content: &[u8]) -> Result<T, ContentDecodeError> {
+    ciborium::from_reader(content).map_err(|e| ContentDecodeError::Malformed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::types::output::ResourceMetadata;
+
+    fn resource(resource_type: &str, media_type: &str, content: &[u8]) -> Resource {
+        Resource {
+            metadata: ResourceMetadata {
+                resource_uri: String::from(
+                    "did:ethr:0x1234567890123456789012345678901234567890/resources/1",
+                ),
+                resource_type: resource_type.to_owned(),
+                resource_name: String::from("foo"),
+                resource_id: Some(String::from("1")),
+                resource_collection_id: None,
+                resource_version_id: None,
+                media_type: media_type.to_owned(),
+                created: Utc::now(),
+                checksum: None,
+                previous_version_id: None,
+                next_version_id: None,
+                content_encoding: None,
+                deactivated: false,
+                controller_at_creation: None,
+            },
+            content: content.to_vec(),
+        }
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Degree {
+        name: String,
+    }
+
+    #[test]
+    fn test_decode_by_media_type() {
+        let decoders = ContentDecoders::default();
+
+        let json_resource = resource("bar", "application/ld+json", br#"{"name":"law"}"#);
+        assert!(matches!(
+            json_resource.decode_content(&decoders).unwrap(),
+            ResourceContent::Json(value) if value == json!({"name": "law"})
+        ));
+        assert_eq!(
+            json_resource.decode::<Degree>().unwrap(),
+            Degree {
+                name: String::from("law")
+            }
+        );
+
+        let text_resource = resource("bar", "text/markdown; charset=utf-8", b"# law");
+        assert!(matches!(
+            text_resource.decode_content(&decoders).unwrap(),
+            ResourceContent::Text(text) if text == "# law"
+        ));
+        assert_eq!(text_resource.text().unwrap(), "# law");
+
+        let binary_resource = resource("bar", "application/octet-stream", &[1, 2, 3]);
+        assert!(matches!(
+            binary_resource.decode_content(&decoders).unwrap(),
+            ResourceContent::Bytes(bytes) if bytes == [1, 2, 3]
+        ));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let text_resource = resource("bar", "text/plain", b"law");
+        assert!(matches!(
+            text_resource.decode::<Degree>(),
+            Err(ContentDecodeError::MediaTypeMismatch { .. })
+        ));
+        let json_resource = resource("bar", "application/json", br#"{"name":1}"#);
+        assert!(matches!(
+            json_resource.text(),
+            Err(ContentDecodeError::MediaTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            json_resource.decode::<Degree>(),
+            Err(ContentDecodeError::Malformed(_))
+        ));
+        assert!(matches!(
+            resource("bar", "json", b"{}").decode::<Degree>(),
+            Err(ContentDecodeError::InvalidMediaType(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_decoders() {
+        let mut decoders = ContentDecoders::default();
+        decoders.register_resource_type("degree", |content| {
+            let name = decode_text(content)?;
+            Ok(ResourceContent::custom(Degree { name }))
+        });
+
+        let content = resource("degree", "application/octet-stream", b"law")
+            .decode_content(&decoders)
+            .unwrap();
+        assert_eq!(
+            content.downcast_ref::<Degree>(),
+            Some(&Degree {
+                name: String::from("law")
+            })
+        );
+
+        // other resource types are decoded by media type
+        assert!(matches!(
+            resource("bar", "text/plain", b"law")
+                .decode_content(&decoders)
+                .unwrap(),
+            ResourceContent::Text(_)
+        ));
+        assert!(matches!(
+            resource("bar", "text/plain", b"law")
+                .decode_content(&ContentDecoders::empty())
+                .unwrap(),
+            ResourceContent::Bytes(_)
+        ));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_decode_cbor() {
+        let mut content = vec![];
+        ciborium::into_writer(&json!({"name": "law"}), &mut content).unwrap();
+
+        let cbor_resource = resource("bar", "application/cbor", &content);
+        assert_eq!(
+            cbor_resource.decode::<Degree>().unwrap(),
+            Degree {
+                name: String::from("law")
+            }
+        );
+        assert!(matches!(
+            cbor_resource
+                .decode_content(&ContentDecoders::default())
+                .unwrap(),
+            ResourceContent::Cbor(_)
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+//! Tracing spans (and, with the `metrics` feature, metrics) for resolves and writes.
+//!
+//! Every resolve runs in a `resolve` span, and every write in a `write` span, recording the
+//! RPC calls made, latency and outcome (and gas used, for writes). With the `metrics` feature,
+//! the same are reported through the [metrics](https://docs.rs/metrics) facade, under the names
+//! below, to whichever exporter the application installs.
+//!
+//! RPC calls are counted at the transport, by a [CountingClient]. Providers built by this crate
+//! (from a `rpc_url`) use one; to count the calls of a `with_client` client or of a signer
+//! (e.g. gas estimation & sending for writes), wrap its transport in a [CountingClient]:
+//!
+//! ```ignore
+//! let provider = Provider::new(CountingClient::new(MultiRpcClient::new(&multi_rpc_config)?));
+//! ```
+
+use std::{
+    cell::Cell,
+    fmt::{Debug, Display},
+    future::Future,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethers::{
+    providers::{Http, JsonRpcClient, Provider},
+    types::{H256, U256},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{field::Empty, Instrument, Span};
+
+/// Counter of resolves, labelled by `backend` and `outcome` (`ok` or `error`).
+pub const RESOLUTIONS: &str = "dlr_resolutions_total";
+/// Histogram of resolve latency (seconds), labelled by `backend`.
+pub const RESOLUTION_DURATION: &str = "dlr_resolution_duration_seconds";
+/// Counter of writes, labelled by `operation` and `outcome` (`ok` or `error`).
+pub const WRITES: &str = "dlr_writes_total";
+/// Histogram of write latency (seconds), labelled by `operation`.
+pub const WRITE_DURATION: &str = "dlr_write_duration_seconds";
+/// Histogram of the gas used by resource writing transactions.
+pub const WRITE_GAS_USED: &str = "dlr_write_gas_used";
+/// Counter of RPC calls, labelled by `method`.
+pub const RPC_CALLS: &str = "dlr_rpc_calls_total";
+/// Counter of cache lookups, labelled by `cache` and `outcome` (`hit` or `miss`).
+pub const CACHE_LOOKUPS: &str = "dlr_cache_lookups_total";
+
+tokio::task_local! {
+    /// RPC calls made within the current [count_rpc_calls]
+    static RPC_CALL_COUNT: Cell<u64>;
+}
+
+/// Run `operation`, counting the RPC calls it makes (see [rpc_call]). Calls made within nested
+/// counts are also counted by the enclosing count.
+async fn count_rpc_calls<F: Future>(operation: F) -> (F::Output, u64) {
+    let (output, count) = RPC_CALL_COUNT
+        .scope(Cell::new(0), async {
+            let output = operation.await;
+            (output, RPC_CALL_COUNT.with(Cell::get))
+        })
+        .await;
+    let _ = RPC_CALL_COUNT.try_with(|outer| outer.set(outer.get() + count));
+    (output, count)
+}
+
+/// Record an RPC call of the JSON-RPC `method`.
+fn rpc_call(method: &str) {
+    let _ = RPC_CALL_COUNT.try_with(|count| count.set(count.get() + 1));
+    tracing::trace!(method, "rpc call");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(RPC_CALLS, "method" => method.to_owned()).increment(1);
+}
+
+/// The provider built by this crate for a `rpc_url`: HTTP, with RPC calls counted.
+pub type HttpProvider = Provider<CountingClient<Http>>;
+
+/// Build a [HttpProvider] for the `rpc_url`.
+pub fn http_provider(rpc_url: &str) -> Result<HttpProvider, url::ParseError> {
+    Ok(Provider::new(CountingClient::new(Http::from_str(rpc_url)?)))
+}
+
+/// A transport which records every request it sends as an RPC call, counted by the enclosing
+/// `resolve` or `write` span (and, with the `metrics` feature, by [RPC_CALLS]).
+#[derive(Clone, Debug)]
+pub struct CountingClient<C> {
+    inner: C,
+}
+
+impl<C> CountingClient<C> {
+    pub fn new(inner: C) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<C: JsonRpcClient> JsonRpcClient for CountingClient<C> {
+    type Error = C::Error;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        rpc_call(method);
+        self.inner.request(method, params).await
+    }
+}
+
+/// Record a lookup of `cache`.
+pub(crate) fn cache_lookup(cache: &'static str, hit: bool) {
+    let outcome = if hit { "hit" } else { "miss" };
+    tracing::trace!(cache, outcome, "cache lookup");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(CACHE_LOOKUPS, "cache" => cache, "outcome" => outcome).increment(1);
+}
+
+/// Record the hash of the transaction sent by the current `write` span.
+pub(crate) fn tx_hash(tx_hash: H256) {
+    Span::current().record("tx_hash", tracing::field::debug(tx_hash));
+}
+
+/// Record the gas used by a resource writing transaction, on the current `write` span.
+pub(crate) fn gas_used(gas_used: U256) {
+    Span::current().record("gas_used", gas_used.as_u64());
+
+    #[cfg(feature = "metrics")]
+    metrics::histogram!(WRITE_GAS_USED).record(gas_used.as_u64() as f64);
+}
+
+/// Run the resolve of `query` within a `resolve` span.
+pub(crate) async fn resolve<T, E: Display>(
+    query: &str,
+    backend: &'static str,
+    operation: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let span = tracing::info_span!(
+        "resolve",
+        query,
+        backend,
+        rpc_calls = Empty,
+        latency_ms = Empty,
+        outcome = Empty,
+    );
+    let start = Instant::now();
+    let (result, rpc_calls) = count_rpc_calls(operation).instrument(span.clone()).await;
+    let latency = start.elapsed();
+    record_result(&span, &result, rpc_calls, latency);
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!(RESOLUTIONS, "backend" => backend, "outcome" => outcome(&result))
+            .increment(1);
+        metrics::histogram!(RESOLUTION_DURATION, "backend" => backend)
+            .record(latency.as_secs_f64());
+    }
+
+    result
+}
+
+/// Run the write `operation` (for the `did`, if known) within a `write` span.
+pub(crate) async fn write<T, E: Display>(
+    operation: &'static str,
+    did: Option<&str>,
+    write: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let span = tracing::info_span!(
+        "write",
+        operation,
+        did,
+        tx_hash = Empty,
+        rpc_calls = Empty,
+        gas_used = Empty,
+        latency_ms = Empty,
+        outcome = Empty,
+    );
+    let start = Instant::now();
+    let (result, rpc_calls) = count_rpc_calls(write).instrument(span.clone()).await;
+    let latency = start.elapsed();
+    record_result(&span, &result, rpc_calls, latency);
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!(WRITES, "operation" => operation, "outcome" => outcome(&result))
+            .increment(1);
+        metrics::histogram!(WRITE_DURATION, "operation" => operation).record(latency.as_secs_f64());
+    }
+
+    result
+}
+
+fn outcome<T, E>(result: &Result<T, E>) -> &'static str {
+    if result.is_ok() {
+        "ok"
+    } else {
+        "error"
+    }
+}
+
+fn record_result<T, E: Display>(
+    span: &Span,
+    result: &Result<T, E>,
+    rpc_calls: u64,
+    latency: Duration,
+) {
+    span.record("rpc_calls", rpc_calls);
+    span.record("latency_ms", latency.as_millis() as u64);
+    span.record("outcome", outcome(result));
+    if let Err(e) = result {
+        tracing::debug!(parent: span, error = %e, "failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{
+        providers::{Middleware, MockProvider, Provider},
+        types::U64,
+    };
+
+    use super::{count_rpc_calls, rpc_call, CountingClient};
+
+    #[tokio::test]
+    async fn test_count_rpc_calls() {
+        let (inner_count, outer_count) = count_rpc_calls(async {
+            rpc_call("eth_blockNumber");
+            let ((), inner_count) = count_rpc_calls(async {
+                rpc_call("eth_call");
+                rpc_call("eth_getLogs");
+            })
+            .await;
+            rpc_call("eth_chainId");
+            inner_count
+        })
+        .await;
+
+        assert_eq!(inner_count, 2);
+        assert_eq!(outer_count, 4);
+
+        // outside of a count
+        rpc_call("eth_call");
+    }
+
+    #[tokio::test]
+    async fn test_counting_client() {
+        let transport = MockProvider::new();
+        transport.push(U64::from(2)).unwrap();
+        transport.push(U64::from(1)).unwrap();
+        let provider = Provider::new(CountingClient::new(transport));
+
+        let ((), count) = count_rpc_calls(async {
+            provider.get_block_number().await.unwrap();
+            provider.get_block_number().await.unwrap();
+        })
+        .await;
+
+        assert_eq!(count, 2);
+    }
+}
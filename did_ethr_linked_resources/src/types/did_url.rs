@@ -0,0 +1,287 @@
+//! A parser of DID URLs, following the [DID Core](https://www.w3.org/TR/did-core/#did-url-syntax)
+//! ABNF:
+//!
+//! ```text
+//! did-url            = did path-abempty [ "?" query ] [ "#" fragment ]
+//! did                = "did:" method-name ":" method-specific-id
+//! method-name        = 1*method-char
+//! method-char        = %x61-7A / DIGIT
+//! method-specific-id = *( *idchar ":" ) 1*idchar
+//! idchar             = ALPHA / DIGIT / "." / "-" / "_" / pct-encoded
+//! ```
+//!
+//! with `path-abempty`, `query` & `fragment` as in [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-3.3).
+
+use crate::error::DidUrlError;
+
+/// A syntactically valid DID URL, with its path segments, query parameters and fragment
+/// percent-decoded.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DidUrl {
+    pub method: String,
+    /// the method specific identifier, as is (it may contain percent-encoded characters)
+    pub method_specific_id: String,
+    pub path_segments: Vec<String>,
+    /// the `name=value` pairs of the query, in order (a parameter without a `=` has an empty
+    /// value)
+    pub query: Vec<(String, String)>,
+    pub fragment: Option<String>,
+}
+
+impl DidUrl {
+    pub fn parse(did_url: &str) -> Result<Self, DidUrlError> {
+        let rest = did_url
+            .strip_prefix("did:")
+            .ok_or_else(|| DidUrlError::InvalidDid(String::from("must start with `did:`")))?;
+        let mut position = "did:".len();
+
+        // method-name ":"
+        let method_len = rest
+            .find(|c: char| !is_method_char(c))
+            .unwrap_or(rest.len());
+        let (method, rest) = rest.split_at(method_len);
+        position += method_len;
+        let rest = match (rest.chars().next(), method.is_empty()) {
+            (Some(':'), false) => &rest[1..],
+            (Some(':') | None, true) => {
+                return Err(DidUrlError::InvalidDid(String::from("missing method name")))
+            }
+            (None, false) => {
+                return Err(DidUrlError::InvalidDid(String::from(
+                    "missing method specific identifier",
+                )))
+            }
+            (Some(character), _) => {
+                return Err(DidUrlError::InvalidCharacter {
+                    position,
+                    character,
+                })
+            }
+        };
+        position += 1;
+
+        // method-specific-id
+        let (method_specific_id, rest) =
+            rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+        decode(method_specific_id, position, |c| is_idchar(c) || c == ':')?;
+        if method_specific_id.is_empty() || method_specific_id.ends_with(':') {
+            return Err(DidUrlError::InvalidDid(String::from(
+                "method specific identifier must not be empty, nor end with `:`",
+            )));
+        }
+        position += method_specific_id.len();
+
+        // path-abempty
+        let (path, rest) = rest.split_at(rest.find(['?', '#']).unwrap_or(rest.len()));
+        let mut path_segments = Vec::new();
+        let mut segment_position = position;
+        for segment in path.split('/').skip(1) {
+            segment_position += 1;
+            path_segments.push(decode(segment, segment_position, is_pchar)?);
+            segment_position += segment.len();
+        }
+        position += path.len();
+
+        // [ "?" query ]
+        let (query, rest) = rest.split_at(rest.find('#').unwrap_or(rest.len()));
+        let mut query_pairs = Vec::new();
+        if let Some(query) = query.strip_prefix('?') {
+            let mut pair_position = position + 1;
+            for pair in query.split('&') {
+                if !pair.is_empty() {
+                    let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    let value_position = pair_position + name.len() + 1;
+                    query_pairs.push((
+                        decode(name, pair_position, is_query_char)?,
+                        decode(value, value_position, is_query_char)?,
+                    ));
+                }
+                pair_position += pair.len() + 1;
+            }
+        }
+        position += query.len();
+
+        // [ "#" fragment ]
+        let fragment = rest
+            .strip_prefix('#')
+            .map(|fragment| decode(fragment, position + 1, is_query_char))
+            .transpose()?;
+
+        Ok(Self {
+            method: method.to_owned(),
+            method_specific_id: method_specific_id.to_owned(),
+            path_segments,
+            query: query_pairs,
+            fragment,
+        })
+    }
+}
+
+fn is_method_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit()
+}
+
+fn is_idchar(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')
+}
+
+fn is_pchar(c: char) -> bool {
+    let unreserved = c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~');
+    let sub_delim = matches!(
+        c,
+        '!' | '$' | '&' | '\'' | '(' | ')' | '*' | '+' | ',' | ';' | '='
+    );
+    unreserved || sub_delim || matches!(c, ':' | '@')
+}
+
+fn is_query_char(c: char) -> bool {
+    is_pchar(c) || matches!(c, '/' | '?')
+}
+
+/// Percent-decode the `component` (at byte `position` of the DID URL), which may only contain
+/// `allowed` characters and percent-encoded (UTF-8) bytes.
+fn decode(
+    component: &str,
+    position: usize,
+    allowed: impl Fn(char) -> bool,
+) -> Result<String, DidUrlError> {
+    let mut bytes = Vec::with_capacity(component.len());
+    let mut chars = component.char_indices();
+    while let Some((i, character)) = chars.next() {
+        if character == '%' {
+            let byte = component
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or(DidUrlError::InvalidPercentEncoding {
+                    position: position + i,
+                })?;
+            bytes.push(byte);
+            chars.nth(1);
+        } else if allowed(character) {
+            bytes.push(character as u8);
+        } else {
+            return Err(DidUrlError::InvalidCharacter {
+                position: position + i,
+                character,
+            });
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| DidUrlError::InvalidPercentEncoding { position })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::DidUrl;
+    use crate::error::DidUrlError;
+
+    #[test]
+    fn test_parse_did_url() {
+        let did_url = DidUrl::parse(
+            "did:ethr:0x5:0xb9c5714089478a327f09197987f16f9e5d936e8a/resources/a%20b?service=files&relativeRef=%2Fx&foo#key-1",
+        )
+        .unwrap();
+
+        assert_eq!(did_url.method, "ethr");
+        assert_eq!(
+            did_url.method_specific_id,
+            "0x5:0xb9c5714089478a327f09197987f16f9e5d936e8a"
+        );
+        assert_eq!(did_url.path_segments, ["resources", "a b"]);
+        assert_eq!(
+            did_url.query,
+            [
+                (String::from("service"), String::from("files")),
+                (String::from("relativeRef"), String::from("/x")),
+                (String::from("foo"), String::new()),
+            ]
+        );
+        assert_eq!(did_url.fragment.as_deref(), Some("key-1"));
+
+        let did_url = DidUrl::parse("did:example:123").unwrap();
+        assert!(did_url.path_segments.is_empty());
+        assert!(did_url.query.is_empty());
+        assert!(did_url.fragment.is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_did_urls() {
+        let invalid_did_urls = [
+            (
+                "https://example.com",
+                DidUrlError::InvalidDid(String::new()),
+            ),
+            ("did:", DidUrlError::InvalidDid(String::new())),
+            ("did:ethr", DidUrlError::InvalidDid(String::new())),
+            ("did:ethr:", DidUrlError::InvalidDid(String::new())),
+            ("did:ethr:0x5:", DidUrlError::InvalidDid(String::new())),
+            (
+                "did:Ethr:0x1",
+                DidUrlError::InvalidCharacter {
+                    position: 4,
+                    character: 'E',
+                },
+            ),
+            (
+                "did:ethr:0x 1",
+                DidUrlError::InvalidCharacter {
+                    position: 11,
+                    character: ' ',
+                },
+            ),
+            (
+                "did:ethr:0x1/a b",
+                DidUrlError::InvalidCharacter {
+                    position: 14,
+                    character: ' ',
+                },
+            ),
+            (
+                "did:ethr:0x1?a=ü",
+                DidUrlError::InvalidCharacter {
+                    position: 15,
+                    character: 'ü',
+                },
+            ),
+            (
+                "did:ethr:0x1?a=%2",
+                DidUrlError::InvalidPercentEncoding { position: 15 },
+            ),
+            (
+                "did:ethr:0x1#%zz",
+                DidUrlError::InvalidPercentEncoding { position: 13 },
+            ),
+            (
+                "did:ethr:0x1#%ff",
+                DidUrlError::InvalidPercentEncoding { position: 13 },
+            ),
+        ];
+
+        for (did_url, expected_error) in invalid_did_urls {
+            let error = DidUrl::parse(did_url).unwrap_err();
+            match expected_error {
+                DidUrlError::InvalidDid(_) => {
+                    assert!(matches!(error, DidUrlError::InvalidDid(_)), "{did_url}")
+                }
+                expected_error => assert_eq!(error, expected_error, "{did_url}"),
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_arbitrary_input(input in any::<String>()) {
+            let _ = DidUrl::parse(&input);
+        }
+
+        #[test]
+        fn test_parse_did_like_input(input in "did:[a-z0-9:]{0,8}[a-zA-Z0-9._%:/?#&=+-]{0,32}") {
+            if let Ok(did_url) = DidUrl::parse(&input) {
+                prop_assert!(!did_url.method.is_empty());
+                prop_assert!(!did_url.method_specific_id.is_empty());
+            }
+        }
+    }
+}
@@ -1,10 +1,37 @@
-#[derive(Debug, Clone)]
+use std::error::Error;
+
+use ethers::{
+    signers::LocalWallet,
+    types::{Signature, U256},
+};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::ContractNetworkConfig,
+    content_encoding::{encode_resource_input, ContentEncoding, CONTENT_ENCODING_PARAM},
+    contracts::ethr_dlr_registry::create_resource_signed_hash,
+    error::ResourceInputError,
+    utils::full_did_into_did_identity,
+};
+
+/// Maximum length (in bytes) of a resource name, see [ResourceInput::validate].
+pub const MAX_RESOURCE_NAME_LENGTH: usize = 128;
+
+/// Maximum length (in bytes) of a resource type, see [ResourceInput::validate].
+pub const MAX_RESOURCE_TYPE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceInput {
     pub resource_name: String,
     pub resource_type: String,
     pub resource_version_id: String,
     pub media_type: String,
     pub content: Vec<u8>,
+    /// If set, the content is compressed with this encoding on the ledger (recorded in the
+    /// on-chain media type). Resolved resources always hold the original content.
+    #[serde(default)]
+    pub content_encoding: Option<ContentEncoding>,
 }
 
 impl Default for ResourceInput {
@@ -15,6 +42,255 @@ impl Default for ResourceInput {
             resource_version_id: Default::default(),
             media_type: Default::default(),
             content: Default::default(),
+            content_encoding: Default::default(),
+        }
+    }
+}
+
+impl ResourceInput {
+    /// Build a [ResourceInput], validated on [ResourceInputBuilder::build].
+    pub fn builder() -> ResourceInputBuilder {
+        ResourceInputBuilder::default()
+    }
+
+    /// Check the name, type and media type of the resource are well formed:
+    /// * names are 1-[MAX_RESOURCE_NAME_LENGTH] ASCII alphanumerics, spaces or `-._~` (without
+    ///   leading or trailing spaces)
+    /// * types are 1-[MAX_RESOURCE_TYPE_LENGTH] ASCII alphanumerics or `-._~`
+    /// * media types are valid MIME types, without a `content-encoding` parameter (use
+    ///   [ResourceInput::content_encoding] instead)
+    ///
+    /// Inputs built with [ResourceInput::builder] are always valid. Inputs constructed directly
+    /// are not validated by the registrar, and are written as-is.
+    pub fn validate(&self) -> Result<(), ResourceInputError> {
+        validate_identifier(&self.resource_name, MAX_RESOURCE_NAME_LENGTH, true)
+            .map_err(ResourceInputError::InvalidName)?;
+        validate_identifier(&self.resource_type, MAX_RESOURCE_TYPE_LENGTH, false)
+            .map_err(ResourceInputError::InvalidType)?;
+        self.mime()?;
+        Ok(())
+    }
+
+    /// The media type of the resource, parsed as a MIME type.
+    pub fn mime(&self) -> Result<Mime, ResourceInputError> {
+        let mime: Mime = self.media_type.parse().map_err(|e| {
+            ResourceInputError::InvalidMediaType(format!("{:?}: {e}", self.media_type))
+        })?;
+        if mime.get_param(CONTENT_ENCODING_PARAM).is_some() {
+            return Err(ResourceInputError::InvalidMediaType(format!(
+                "{:?}: the {CONTENT_ENCODING_PARAM} parameter is reserved",
+                self.media_type
+            )));
         }
+        Ok(mime)
+    }
+
+    /// Whether this resource would be confused with the resource named `resource_name` of
+    /// `resource_type` by the registry, which keys resources by their concatenated name & type.
+    pub fn is_ambiguous_with(&self, resource_name: &str, resource_type: &str) -> bool {
+        let same_resource =
+            self.resource_name == resource_name && self.resource_type == resource_type;
+        let same_key = format!("{}{}", self.resource_name, self.resource_type)
+            == format!("{resource_name}{resource_type}");
+        same_key && !same_resource
+    }
+}
+
+fn validate_identifier(value: &str, max_length: usize, allow_spaces: bool) -> Result<(), String> {
+    if value.is_empty() {
+        return Err(String::from("must not be empty"));
+    }
+    if value.len() > max_length {
+        return Err(format!("{value:?} is longer than {max_length} bytes"));
+    }
+    let invalid_char = value.chars().find(|c| {
+        !(c.is_ascii_alphanumeric()
+            || matches!(c, '-' | '.' | '_' | '~')
+            || (allow_spaces && *c == ' '))
+    });
+    if let Some(c) = invalid_char {
+        return Err(format!("{value:?} contains unsupported character {c:?}"));
+    }
+    if value.starts_with(' ') || value.ends_with(' ') {
+        return Err(format!("{value:?} has leading or trailing spaces"));
+    }
+    Ok(())
+}
+
+/// Builder for a validated [ResourceInput], see [ResourceInput::builder].
+#[derive(Debug, Clone)]
+pub struct ResourceInputBuilder {
+    resource_input: ResourceInput,
+}
+
+impl Default for ResourceInputBuilder {
+    fn default() -> Self {
+        Self {
+            resource_input: ResourceInput {
+                media_type: mime::APPLICATION_OCTET_STREAM.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl ResourceInputBuilder {
+    pub fn resource_name(mut self, resource_name: impl Into<String>) -> Self {
+        self.resource_input.resource_name = resource_name.into();
+        self
+    }
+
+    pub fn resource_type(mut self, resource_type: impl Into<String>) -> Self {
+        self.resource_input.resource_type = resource_type.into();
+        self
+    }
+
+    pub fn resource_version_id(mut self, resource_version_id: impl Into<String>) -> Self {
+        self.resource_input.resource_version_id = resource_version_id.into();
+        self
+    }
+
+    /// The media type of the content, either a [Mime] (e.g. [mime::APPLICATION_JSON]) or a
+    /// string, which is parsed on [Self::build]. `application/octet-stream` if not set.
+    pub fn media_type(mut self, media_type: impl AsRef<str>) -> Self {
+        self.resource_input.media_type = media_type.as_ref().to_owned();
+        self
+    }
+
+    pub fn content(mut self, content: impl Into<Vec<u8>>) -> Self {
+        self.resource_input.content = content.into();
+        self
+    }
+
+    pub fn content_encoding(mut self, content_encoding: ContentEncoding) -> Self {
+        self.resource_input.content_encoding = Some(content_encoding);
+        self
+    }
+
+    /// Build the [ResourceInput], failing if it is not valid (see [ResourceInput::validate]).
+    pub fn build(self) -> Result<ResourceInput, ResourceInputError> {
+        self.resource_input.validate()?;
+        Ok(self.resource_input)
+    }
+}
+
+/// A [ResourceInput] which has been signed offline by the DID controller, ready to be
+/// submitted to the registry by a relayer (who pays for the transaction).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedResourceInput {
+    pub did: String,
+    pub resource: ResourceInput,
+    /// the signed write nonce of the DID controller at the time of signing
+    pub nonce: U256,
+    pub signature: Signature,
+}
+
+impl SignedResourceInput {
+    /// Sign the `resource` for the `did` with the DID `controller` wallet. `nonce` should be the
+    /// controller's current signed write nonce in the registry of `config`
+    /// (see [crate::registrar::EthrDidLinkedResourcesRegistrar::get_signed_write_nonce]).
+    ///
+    /// Signing is done offline, the controller needs no funds or RPC access.
+    pub fn sign(
+        controller: &LocalWallet,
+        config: &ContractNetworkConfig,
+        did: &str,
+        nonce: U256,
+        resource: ResourceInput,
+    ) -> Result<Self, Box<dyn Error>> {
+        // sign the resource as it will be written to the ledger
+        let resource = encode_resource_input(resource)?;
+        let hash = create_resource_signed_hash(
+            config.contract_address.parse()?,
+            nonce,
+            full_did_into_did_identity(did),
+            &resource,
+        );
+        let signature = controller.sign_hash(hash)?;
+
+        Ok(Self {
+            did: did.to_owned(),
+            resource,
+            nonce,
+            signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn builder() -> ResourceInputBuilder {
+        ResourceInput::builder()
+            .resource_name("my schema-1.0")
+            .resource_type("anonCredsSchema")
+            .media_type(mime::APPLICATION_JSON)
+            .content("{}")
+    }
+
+    #[test]
+    fn test_build_valid_input() {
+        let resource_input = builder().resource_version_id("1.0").build().unwrap();
+        assert_eq!(resource_input.resource_name, "my schema-1.0");
+        assert_eq!(resource_input.media_type, "application/json");
+        assert_eq!(resource_input.mime().unwrap(), mime::APPLICATION_JSON);
+        assert_eq!(resource_input.content, b"{}");
+
+        let resource_input = builder()
+            .media_type("text/plain; charset=utf-8")
+            .build()
+            .unwrap();
+        assert_eq!(resource_input.mime().unwrap(), mime::TEXT_PLAIN_UTF_8);
+
+        let resource_input = ResourceInput::builder()
+            .resource_name("foo")
+            .resource_type("bar")
+            .build()
+            .unwrap();
+        assert_eq!(
+            resource_input.mime().unwrap(),
+            mime::APPLICATION_OCTET_STREAM
+        );
+    }
+
+    #[test]
+    fn test_build_invalid_input() {
+        let invalid_names = ["", " foo", "foo?", "foo/bar", "föo", &"a".repeat(129)];
+        for name in invalid_names {
+            assert!(matches!(
+                builder().resource_name(name).build(),
+                Err(ResourceInputError::InvalidName(_))
+            ));
+        }
+
+        let invalid_types = ["", "foo bar", "foo#chunk", &"a".repeat(65)];
+        for resource_type in invalid_types {
+            assert!(matches!(
+                builder().resource_type(resource_type).build(),
+                Err(ResourceInputError::InvalidType(_))
+            ));
+        }
+
+        let invalid_media_types = ["", "json", "application/json;content-encoding=zstd"];
+        for media_type in invalid_media_types {
+            assert!(matches!(
+                builder().media_type(media_type).build(),
+                Err(ResourceInputError::InvalidMediaType(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_is_ambiguous_with() {
+        let resource_input = ResourceInput {
+            resource_name: String::from("ab"),
+            resource_type: String::from("c"),
+            ..Default::default()
+        };
+        assert!(resource_input.is_ambiguous_with("a", "bc"));
+        assert!(resource_input.is_ambiguous_with("abc", ""));
+        assert!(!resource_input.is_ambiguous_with("ab", "c"));
+        assert!(!resource_input.is_ambiguous_with("a", "b"));
     }
 }
@@ -1,3 +1,4 @@
+pub mod did_url;
 pub mod input;
 pub mod output;
 pub mod query;
@@ -1,5 +1,9 @@
 use chrono::offset::Utc;
 use chrono::DateTime;
+use ethers::types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::content_encoding::ContentEncoding;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Resource {
@@ -48,4 +52,60 @@ pub struct ResourceMetadata {
     /// The value of the property MUST be an string. The value must be 'null' if there is no next version.
     /// For example: null
     pub next_version_id: Option<String>,
+    /// Not part of the spec. The encoding the content is compressed with on the ledger, if any.
+    /// [Resource::content] always holds the decoded content.
+    pub content_encoding: Option<ContentEncoding>,
+    /// Not part of the spec. Whether the resource (by name & type) was deactivated at the version
+    /// time it was resolved at, i.e. its latest version at that time is a tombstone. Versions
+    /// resolved by id are resolved as of their creation, so only tombstones are flagged.
+    pub deactivated: bool,
+    /// Not part of the spec. Who controlled the DID when the resource was written, if audited
+    /// (see [crate::resolver::ResolutionOptions::audit_controller]).
+    pub controller_at_creation: Option<ControllerAtCreation>,
+}
+
+/// The controller of a resource's DID at the time the resource was written, as recorded by the
+/// `EthereumDIDRegistry`.
+///
+/// Verifiers can use this to reject resources written while a (since rotated) controller key
+/// was compromised.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControllerAtCreation {
+    /// owner of the DID when the resource was written
+    pub controller: H160,
+    /// sender of the transaction which wrote the resource
+    pub tx_sender: H160,
+    /// block the resource was written in
+    pub block_number: u64,
+}
+
+impl ControllerAtCreation {
+    /// Whether the transaction which wrote the resource was sent by the DID controller. This is
+    /// not the case for resources written by a delegate, or relayed (signed) writes.
+    pub fn sent_by_controller(&self) -> bool {
+        self.controller == self.tx_sender
+    }
+}
+
+/// Estimated cost of writing a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResourceCostEstimate {
+    /// Estimated gas units used by the write.
+    pub gas: U256,
+    /// `maxFeePerGas` (wei) the write would be sent with, according to the fee strategy.
+    pub max_fee_per_gas: U256,
+    /// `maxPriorityFeePerGas` (wei) the write would be sent with, according to the fee strategy.
+    pub max_priority_fee_per_gas: U256,
+    /// Upper bound for the cost of the write (wei), i.e. `gas * max_fee_per_gas`.
+    pub max_cost_wei: U256,
+}
+
+/// A resource write which has been broadcast to the ledger, but may not yet be mined.
+///
+/// Can be persisted, and later resumed with
+/// [crate::registrar::EthrDidLinkedResourcesRegistrar::await_resource].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingResource {
+    pub tx_hash: H256,
+    pub nonce: U256,
 }
@@ -1,11 +1,15 @@
-use std::error::Error;
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 use chrono::offset::Utc;
-use chrono::DateTime;
+use chrono::{DateTime, SecondsFormat};
 use ethers::types::H160;
-use url::Url;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{error::DidUrlError, types::did_url::DidUrl};
+
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct ResourceQueryParameters {
     pub resource_id: Option<String>,
     pub resource_name: Option<String>,
@@ -17,91 +21,296 @@ pub struct ResourceQueryParameters {
     pub resource_metadata: Option<bool>,
     pub latest_resource_version: Option<bool>,
     pub all_resource_versions: Option<bool>,
+    /// the DID Core `service` parameter, selecting a service of the DID document
+    pub service: Option<String>,
+    /// the DID Core `relativeRef` parameter, a relative reference within the `service`
+    pub relative_ref: Option<String>,
+    /// any other parameters, in order, which are passed through as is
+    pub other_parameters: Vec<(String, String)>,
 }
 
+/// A DID URL query of resources, which displays as the DID URL (see [Self::to_did_url]), such
+/// that `ResourceQuery::parse_from_str(&query.to_string())` is the `query`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ResourceQuery {
+    /// the did:ethr network (sub method) of the DID, e.g. `local` or `0x5`, if not mainnet
+    pub network: Option<String>,
     pub did_identity: H160,
     pub parameters: ResourceQueryParameters,
+    pub fragment: Option<String>,
 }
 
 impl ResourceQuery {
-    pub fn parse_from_str(did_query: &str) -> Result<Self, Box<dyn Error>> {
-        let mut query_params = ResourceQueryParameters {
-            resource_id: None,
-            resource_name: None,
-            resource_type: None,
-            resource_version_id: None,
-            version_time: None,
-            version_id: None,
-            linked_resource: None,
-            resource_metadata: None,
-            latest_resource_version: None,
-            all_resource_versions: None,
-        };
+    /// Build a query of the resources of the `did`, e.g.:
+    /// ```ignore
+    /// let query = ResourceQuery::builder(&did)?
+    ///     .resource_name("degreeLaw")
+    ///     .resource_type("JSONSchema2020")
+    ///     .version_time(version_time)
+    ///     .build()
+    ///     .to_did_url();
+    /// ```
+    pub fn builder(did: &str) -> Result<ResourceQueryBuilder, DidUrlError> {
+        let query = Self::parse_from_str(did)?;
+        if query.parameters != ResourceQueryParameters::default() || query.fragment.is_some() {
+            return Err(DidUrlError::InvalidDid(format!(
+                "{did:?} is a DID URL, not a DID"
+            )));
+        }
+        Ok(ResourceQueryBuilder { query })
+    }
 
-        let did_query_url = Url::parse(did_query)?;
+    /// Parse a did:ethr DID URL (see [DidUrl]), with an optional `/resources/<resource id>`
+    /// path.
+    pub fn parse_from_str(did_query: &str) -> Result<Self, DidUrlError> {
+        let did_url = DidUrl::parse(did_query)?;
 
-        if did_query_url.scheme() != "did" {
-            return Err(format!("Invalid DID query: {}", did_query).into());
+        if did_url.method != "ethr" {
+            return Err(DidUrlError::UnsupportedMethod(did_url.method));
         }
 
-        let did_query_path = did_query_url.path();
-        let mut did_query_path_parts = did_query_path.split("/");
-        let method_and_did = did_query_path_parts
-            .next()
-            .ok_or("Could not parse DID query: missing method and DID")?;
-        // TODO - assert ethr method?
-        let did_identity_hex_str = method_and_did
-            .split(":")
-            .last()
-            .ok_or(format!("Could not read find author of DID: {did_query}"))?;
-        let did_identity = did_identity_hex_str.parse().unwrap();
-
-        match (
-            did_query_path_parts.next(),
-            did_query_path_parts.next(),
-            did_query_path_parts.next(),
-        ) {
-            (None, _, _) => {}
-            (Some("resources"), Some(resource_id), None) => {
-                query_params.resource_id = Some(resource_id.to_owned());
+        // ethr-specific-id = [ network ":" ] ethereum-address
+        let (network, identifier) = match did_url.method_specific_id.rsplit_once(':') {
+            Some((network, identifier)) => (Some(network), identifier),
+            None => (None, did_url.method_specific_id.as_str()),
+        };
+        if let Some(network) = network {
+            if network.is_empty() || network.contains([':', '%']) {
+                return Err(DidUrlError::InvalidIdentifier(format!(
+                    "invalid network {network:?}"
+                )));
+            }
+        }
+        let did_identity = parse_address(identifier)?;
+
+        let mut query_params = ResourceQueryParameters::default();
+
+        match &did_url.path_segments[..] {
+            [] => {}
+            [resources, resource_id] if resources == "resources" => {
+                query_params.resource_id = Some(resource_id.clone());
             }
-            _ => {
-                return Err(format!("Invalid DID query: {}", did_query).into());
+            segments => {
+                return Err(DidUrlError::InvalidPath(format!("/{}", segments.join("/"))));
             }
         }
 
-        for (name, value) in did_query_url.query_pairs() {
-            match name.as_ref() {
-                "resourceId" => query_params.resource_id = Some(value.into_owned()),
-                "resourceName" => query_params.resource_name = Some(value.into_owned()),
-                "resourceType" => query_params.resource_type = Some(value.into_owned()),
-                "resourceVersionId" => query_params.resource_version_id = Some(value.into_owned()),
-                "versionTime" => query_params.version_time = Some(value.parse::<DateTime<Utc>>()?),
-                "versionId" => query_params.version_id = Some(value.into_owned()),
-                "linkedResource" => query_params.linked_resource = Some(value.parse::<bool>()?),
-                "resourceMetadata" => query_params.resource_metadata = Some(value.parse::<bool>()?),
+        for (name, value) in did_url.query {
+            match name.as_str() {
+                "resourceId" => set_param(&mut query_params.resource_id, &name, value)?,
+                "resourceName" => set_param(&mut query_params.resource_name, &name, value)?,
+                "resourceType" => set_param(&mut query_params.resource_type, &name, value)?,
+                "resourceVersionId" => {
+                    set_param(&mut query_params.resource_version_id, &name, value)?
+                }
+                "versionTime" => {
+                    let version_time = parse_param::<DateTime<Utc>>(&name, &value)?;
+                    set_param(&mut query_params.version_time, &name, version_time)?
+                }
+                "versionId" => set_param(&mut query_params.version_id, &name, value)?,
+                "linkedResource" => {
+                    let value = parse_param(&name, &value)?;
+                    set_param(&mut query_params.linked_resource, &name, value)?
+                }
+                "resourceMetadata" => {
+                    let value = parse_param(&name, &value)?;
+                    set_param(&mut query_params.resource_metadata, &name, value)?
+                }
                 "latestResourceVersion" => {
-                    query_params.latest_resource_version = Some(value.parse::<bool>()?)
+                    let value = parse_param(&name, &value)?;
+                    set_param(&mut query_params.latest_resource_version, &name, value)?
                 }
                 "allResourceVersions" => {
-                    query_params.all_resource_versions = Some(value.parse::<bool>()?)
+                    let value = parse_param(&name, &value)?;
+                    set_param(&mut query_params.all_resource_versions, &name, value)?
                 }
-                _ => return Err(format!("Unknown query parameter: {}", name).into()),
+                "service" => set_param(&mut query_params.service, &name, value)?,
+                "relativeRef" => set_param(&mut query_params.relative_ref, &name, value)?,
+                _ => query_params.other_parameters.push((name, value)),
             }
         }
 
         Ok(ResourceQuery {
+            network: network.map(str::to_owned),
             did_identity,
             parameters: query_params,
+            fragment: did_url.fragment,
         })
     }
+
+    /// The DID URL of the query, with the resource ID (if any) as the path, and the other
+    /// parameters percent-encoded.
+    pub fn to_did_url(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Parse the `0x` prefixed hex address of a did:ethr (public key identifiers are not supported).
+fn parse_address(identifier: &str) -> Result<H160, DidUrlError> {
+    let hex = identifier
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .ok_or_else(|| {
+            DidUrlError::InvalidIdentifier(format!("{identifier:?} is not an Ethereum address"))
+        })?;
+    Ok(H160::from_slice(&hex::decode(hex).expect("checked hex")))
+}
+
+fn set_param<T>(param: &mut Option<T>, name: &str, value: T) -> Result<(), DidUrlError> {
+    if param.is_some() {
+        return Err(DidUrlError::InvalidParameter {
+            name: name.to_owned(),
+            reason: String::from("repeated"),
+        });
+    }
+    *param = Some(value);
+    Ok(())
+}
+
+fn parse_param<T>(name: &str, value: &str) -> Result<T, DidUrlError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value.parse().map_err(|e| DidUrlError::InvalidParameter {
+        name: name.to_owned(),
+        reason: format!("{value:?}: {e}"),
+    })
+}
+
+impl Display for ResourceQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "did:ethr:")?;
+        if let Some(network) = &self.network {
+            write!(f, "{network}:")?;
+        }
+        // debug fmt of the address is the full '0x..' hex encoding
+        write!(f, "{:?}", self.did_identity)?;
+
+        let params = &self.parameters;
+        if let Some(resource_id) = &params.resource_id {
+            write!(f, "/resources/{}", urlencoding::encode(resource_id))?;
+        }
+
+        let query_pairs = [
+            ("resourceName", params.resource_name.clone()),
+            ("resourceType", params.resource_type.clone()),
+            ("resourceVersionId", params.resource_version_id.clone()),
+            (
+                "versionTime",
+                params
+                    .version_time
+                    .map(|time| time.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            ),
+            ("versionId", params.version_id.clone()),
+            (
+                "linkedResource",
+                params.linked_resource.map(|b| b.to_string()),
+            ),
+            (
+                "resourceMetadata",
+                params.resource_metadata.map(|b| b.to_string()),
+            ),
+            (
+                "latestResourceVersion",
+                params.latest_resource_version.map(|b| b.to_string()),
+            ),
+            (
+                "allResourceVersions",
+                params.all_resource_versions.map(|b| b.to_string()),
+            ),
+            ("service", params.service.clone()),
+            ("relativeRef", params.relative_ref.clone()),
+        ];
+        let query_pairs = query_pairs
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)));
+        let other_pairs = params
+            .other_parameters
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()));
+        for (i, (name, value)) in query_pairs.chain(other_pairs).enumerate() {
+            let separator = if i == 0 { '?' } else { '&' };
+            let (name, value) = (urlencoding::encode(name), urlencoding::encode(&value));
+            write!(f, "{separator}{name}={value}")?;
+        }
+
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", urlencoding::encode(fragment))?;
+        }
+        Ok(())
+    }
+}
+
+/// Builder of a [ResourceQuery], see [ResourceQuery::builder].
+#[derive(Debug, Clone)]
+pub struct ResourceQueryBuilder {
+    query: ResourceQuery,
+}
+
+impl ResourceQueryBuilder {
+    pub fn resource_id(mut self, resource_id: impl Into<String>) -> Self {
+        self.query.parameters.resource_id = Some(resource_id.into());
+        self
+    }
+
+    pub fn resource_name(mut self, resource_name: impl Into<String>) -> Self {
+        self.query.parameters.resource_name = Some(resource_name.into());
+        self
+    }
+
+    pub fn resource_type(mut self, resource_type: impl Into<String>) -> Self {
+        self.query.parameters.resource_type = Some(resource_type.into());
+        self
+    }
+
+    pub fn resource_version_id(mut self, resource_version_id: impl Into<String>) -> Self {
+        self.query.parameters.resource_version_id = Some(resource_version_id.into());
+        self
+    }
+
+    pub fn version_time(mut self, version_time: DateTime<Utc>) -> Self {
+        self.query.parameters.version_time = Some(version_time);
+        self
+    }
+
+    pub fn version_id(mut self, version_id: impl Into<String>) -> Self {
+        self.query.parameters.version_id = Some(version_id.into());
+        self
+    }
+
+    pub fn linked_resource(mut self, linked_resource: bool) -> Self {
+        self.query.parameters.linked_resource = Some(linked_resource);
+        self
+    }
+
+    pub fn resource_metadata(mut self, resource_metadata: bool) -> Self {
+        self.query.parameters.resource_metadata = Some(resource_metadata);
+        self
+    }
+
+    pub fn latest_resource_version(mut self, latest_resource_version: bool) -> Self {
+        self.query.parameters.latest_resource_version = Some(latest_resource_version);
+        self
+    }
+
+    pub fn all_resource_versions(mut self, all_resource_versions: bool) -> Self {
+        self.query.parameters.all_resource_versions = Some(all_resource_versions);
+        self
+    }
+
+    pub fn build(self) -> ResourceQuery {
+        self.query
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ResourceQuery;
+    use chrono::{DateTime, TimeZone, Utc};
+    use proptest::prelude::*;
+
+    use super::{ResourceQuery, ResourceQueryParameters};
+    use crate::error::DidUrlError;
 
     #[test]
     fn test_fully_loaded_query() {
@@ -197,4 +406,182 @@ mod tests {
         );
         assert_eq!(query.parameters.resource_metadata.unwrap(), true);
     }
+
+    #[test]
+    fn test_build_query() {
+        let did = "did:ethr:local:0x1234567890123456789012345678901234567890";
+        let query = ResourceQuery::builder(did)
+            .unwrap()
+            .resource_name("my schema?")
+            .resource_type("anonCredsSchema")
+            .version_time(Utc.timestamp_opt(1_700_000_000, 0).unwrap())
+            .resource_metadata(true)
+            .build();
+
+        assert_eq!(
+            query.to_did_url(),
+            format!("{did}?resourceName=my%20schema%3F&resourceType=anonCredsSchema&versionTime=2023-11-14T22%3A13%3A20Z&resourceMetadata=true")
+        );
+        assert_eq!(query.network.as_deref(), Some("local"));
+        assert_eq!(
+            query.parameters.resource_name.as_deref(),
+            Some("my schema?")
+        );
+
+        let query = ResourceQuery::builder(did)
+            .unwrap()
+            .resource_id("42")
+            .build();
+        assert_eq!(query.to_string(), format!("{did}/resources/42"));
+
+        assert!(ResourceQuery::builder(&format!("{did}/resources/42")).is_err());
+        assert!(ResourceQuery::builder("did:ethr:local:0x1234").is_err());
+    }
+
+    #[test]
+    fn test_did_core_parameters() {
+        let query = ResourceQuery::parse_from_str("did:ethr:0x5:0x1234567890123456789012345678901234567890?service=files&relativeRef=%2Fdegree&hl=zQm&resourceName=degreeLaw#key-1").unwrap();
+
+        assert_eq!(query.network.as_deref(), Some("0x5"));
+        assert_eq!(query.parameters.service.as_deref(), Some("files"));
+        assert_eq!(query.parameters.relative_ref.as_deref(), Some("/degree"));
+        assert_eq!(
+            query.parameters.other_parameters,
+            [(String::from("hl"), String::from("zQm"))]
+        );
+        assert_eq!(query.parameters.resource_name.as_deref(), Some("degreeLaw"));
+        assert_eq!(query.fragment.as_deref(), Some("key-1"));
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        let did = "did:ethr:0x1234567890123456789012345678901234567890";
+        let invalid_queries = [
+            "did:web:example.com",
+            "did:ethr:0x1234",
+            "did:ethr:0x02b97c30de767f084ce3080168ee293053ba33b235d7116a3263d29f1450936b71",
+            "did:ethr::0x1234567890123456789012345678901234567890",
+            &format!("{did}/resources"),
+            &format!("{did}/resources/1/2"),
+            &format!("{did}?versionTime=yesterday"),
+            &format!("{did}?resourceMetadata=yes"),
+            &format!("{did}/resources/1?resourceId=2"),
+            &format!("{did}?resourceName=a&resourceName=b"),
+        ];
+        let errors = invalid_queries.map(|query| ResourceQuery::parse_from_str(query).unwrap_err());
+
+        assert!(matches!(&errors[0], DidUrlError::UnsupportedMethod(method) if method == "web"));
+        assert!(errors[1..4]
+            .iter()
+            .all(|e| matches!(e, DidUrlError::InvalidIdentifier(_))));
+        assert!(errors[4..6]
+            .iter()
+            .all(|e| matches!(e, DidUrlError::InvalidPath(_))));
+        assert!(errors[6..]
+            .iter()
+            .all(|e| matches!(e, DidUrlError::InvalidParameter { .. })));
+    }
+
+    fn version_time() -> impl Strategy<Value = DateTime<Utc>> {
+        (
+            0i64..4_000_000_000,
+            prop_oneof![Just(0u32), 0u32..1_000_000_000],
+        )
+            .prop_map(|(secs, nanos)| Utc.timestamp_opt(secs, nanos).unwrap())
+    }
+
+    fn query() -> impl Strategy<Value = ResourceQuery> {
+        let network = proptest::option::of("[a-z]{1,8}|0x[0-9a-f]{1,6}");
+        let did_identity = any::<[u8; 20]>().prop_map(Into::into);
+        let strings = (
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+        );
+        let others = (
+            proptest::option::of(version_time()),
+            proptest::option::of(any::<bool>()),
+            proptest::option::of(any::<bool>()),
+            proptest::option::of(any::<bool>()),
+            proptest::option::of(any::<bool>()),
+        );
+        let known_names = [
+            "resourceId",
+            "resourceName",
+            "resourceType",
+            "resourceVersionId",
+            "versionTime",
+            "versionId",
+            "linkedResource",
+            "resourceMetadata",
+            "latestResourceVersion",
+            "allResourceVersions",
+            "service",
+            "relativeRef",
+        ];
+        let other_parameter = (any::<String>(), any::<String>())
+            .prop_filter("known parameter", move |(name, _)| {
+                !known_names.contains(&name.as_str())
+            });
+        let did_core = (
+            proptest::option::of(any::<String>()),
+            proptest::option::of(any::<String>()),
+            proptest::collection::vec(other_parameter, 0..3),
+            proptest::option::of(any::<String>()),
+        );
+        (network, did_identity, strings, others, did_core).prop_map(
+            |(network, did_identity, strings, others, did_core)| {
+                let (resource_id, resource_name, resource_type, resource_version_id, version_id) =
+                    strings;
+                let (
+                    version_time,
+                    linked_resource,
+                    resource_metadata,
+                    latest_resource_version,
+                    all_resource_versions,
+                ) = others;
+                let (service, relative_ref, other_parameters, fragment) = did_core;
+                ResourceQuery {
+                    network,
+                    did_identity,
+                    parameters: ResourceQueryParameters {
+                        resource_id,
+                        resource_name,
+                        resource_type,
+                        resource_version_id,
+                        version_time,
+                        version_id,
+                        linked_resource,
+                        resource_metadata,
+                        latest_resource_version,
+                        all_resource_versions,
+                        service,
+                        relative_ref,
+                        other_parameters,
+                    },
+                    fragment,
+                }
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn test_query_round_trip(query in query()) {
+            let parsed = ResourceQuery::parse_from_str(&query.to_string()).unwrap();
+            prop_assert_eq!(parsed, query);
+        }
+
+        #[test]
+        fn test_parse_arbitrary_queries(
+            query in "did:ethr:([a-z0-9]{1,4}:)?0x[0-9a-fA-F]{38,42}(/[a-z0-9%]{0,10}){0,3}(\\?[a-zA-Z0-9%=&+:-]{0,40})?(#.{0,5})?"
+        ) {
+            if let Ok(parsed) = ResourceQuery::parse_from_str(&query) {
+                let reparsed = ResourceQuery::parse_from_str(&parsed.to_string()).unwrap();
+                prop_assert_eq!(reparsed, parsed);
+            }
+        }
+    }
 }
@@ -1,5 +1,7 @@
+use std::error::Error;
+
 use chrono::{TimeZone, Utc};
-use ethers::types::H160;
+use ethers::{types::H160, utils::format_bytes32_string};
 
 use crate::{
     contracts::ethr_dlr_registry::{NewResourceFilter, ResourceVersionMetadataChainNode},
@@ -16,6 +18,10 @@ const CHAIN_ID_TO_KNOWN_SUB_METHOD: [(u64, &str); 7] = [
     (31337, "local"),
 ];
 
+/// delegate type of did:ethr delegates which are permitted to sign on behalf of the DID,
+/// see: https://github.com/decentralized-identity/ethr-did-resolver/blob/master/doc/did-method-spec.md#delegate-keys
+pub const VERI_KEY_DELEGATE_TYPE: &str = "veriKey";
+
 // wrapper type of u64 for the sake of clarity in From transformers
 pub(crate) struct ChainId(pub u64);
 
@@ -31,6 +37,18 @@ fn sub_method_name_from_chain_id(chain_id: u64) -> String {
     hex_chain_id
 }
 
+/// whether the did:ethr `network` (sub method, `None` being mainnet) is the network of `chain_id`,
+/// by its known name or hex string chain ID
+pub(crate) fn is_network_of_chain_id(network: Option<&str>, chain_id: u64) -> bool {
+    match network {
+        None => chain_id == 1,
+        Some(network) => {
+            network == sub_method_name_from_chain_id(chain_id)
+                || network == format!("0x{chain_id:x}")
+        }
+    }
+}
+
 /// sub method should be the hex string chain ID of the network, or a known "name":
 /// https://github.com/uport-project/ethr-did-registry#contract-deployments
 pub fn did_identity_as_full_did(address: &H160, chain_id: u64) -> String {
@@ -48,6 +66,12 @@ pub fn full_did_into_did_identity(did: &str) -> H160 {
     identity_hex_str.parse().unwrap()
 }
 
+/// encode a did:ethr delegate type (e.g. "veriKey") into the `bytes32` form used by the
+/// `EthereumDIDRegistry`. (right padded UTF-8 string)
+pub fn delegate_type_as_bytes32(delegate_type: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    Ok(format_bytes32_string(delegate_type)?)
+}
+
 pub fn extract_did_of_dlr_resource_uri(resource_uri: &str) -> String {
     resource_uri.split("/resources").next().unwrap().to_owned()
 }
@@ -99,6 +123,9 @@ impl From<(NewResourceFilter, ResourceVersionMetadataChainNode, ChainId)> for Re
                 checksum: None,
                 previous_version_id,
                 next_version_id,
+                content_encoding: None,
+                deactivated: false,
+                controller_at_creation: None,
             },
         }
     }
@@ -169,6 +196,9 @@ pub mod thegraph {
                     checksum: None,
                     previous_version_id,
                     next_version_id,
+                    content_encoding: None,
+                    deactivated: false,
+                    controller_at_creation: None,
                 },
             }
         }
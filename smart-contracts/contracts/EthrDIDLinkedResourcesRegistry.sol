@@ -9,16 +9,41 @@ contract EthrDIDLinkedResourcesRegistry {
 
     EthereumDIDRegistry public didRegistry;
 
+    // nonce of signed writes, per DID identity owner (replay protection for `createResourceSigned`)
+    mapping(address => uint) public nonce;
+
     modifier onlyDidIdentityOwner(address identity) {
         address actor = msg.sender;
         require (actor == didRegistry.identityOwner(identity), "bad_actor");
         _;
     }
 
-    event NewResource(address indexed didIdentity, uint256 indexed resourceId, string indexed resourceNameAndType, Resource resource);
+    // permits the identity owner, or a currently valid delegate of the given type (e.g. "veriKey")
+    modifier onlyDidIdentityOwnerOrDelegate(address identity, bytes32 delegateType) {
+        address actor = msg.sender;
+        require (actor == didRegistry.identityOwner(identity) || didRegistry.validDelegate(identity, delegateType, actor), "bad_actor");
+        _;
+    }
+
+    // version 1 registries (which have no `REGISTRY_VERSION`) keyed resources by the packed concatenation of their name & type,
+    // so that e.g. ("ab", "c") and ("a", "bc") shared a metadata chain. Since version 2, resources are keyed by `getResourceKey`.
+    uint256 public constant REGISTRY_VERSION = 2;
+
+    // media type of the (empty) tombstone version appended to a resource when it is deactivated
+    string public constant TOMBSTONE_MEDIA_TYPE = "application/vnd.ethr-dlr.tombstone";
+
+    // `resourceKeyPreimage` is `abi.encode(resourceName, resourceType)`, so its (indexed) topic is the `resourceKey`.
+    // The event signature is unchanged from version 1, where the topic was the hash of the concatenated name & type.
+    event NewResource(address indexed didIdentity, uint256 indexed resourceId, string indexed resourceKeyPreimage, Resource resource);
 
-    // (didIdentity -> resourceName+resourceType -> ResourceVersionMetadataChainNode[])
-    mapping(address => mapping(string => ResourceVersionMetadataChainNode[])) private resourceMetadataChains;
+    // emitted (after the tombstone's `NewResource`) when a resource is deactivated, `resourceId` being the tombstone version
+    event ResourceDeactivated(address indexed didIdentity, uint256 indexed resourceId, string indexed resourceKeyPreimage);
+
+    // (didIdentity -> resourceKey -> ResourceVersionMetadataChainNode[])
+    mapping(address => mapping(bytes32 => ResourceVersionMetadataChainNode[])) private resourceMetadataChains;
+
+    // (didIdentity -> resourceKey -> whether the latest version is a tombstone)
+    mapping(address => mapping(bytes32 => bool)) private deactivatedResources;
 
     struct Resource {
         uint256 resourceId;
@@ -35,6 +60,15 @@ contract EthrDIDLinkedResourcesRegistry {
         uint256 metadataChainNodeIndex;
     }
 
+    // input of a single resource within a batch write (`createResources`)
+    struct ResourceInputData {
+        string resourceName;
+        string resourceType;
+        string resourceVersion;
+        string mediaType;
+        bytes content;
+    }
+
     struct ResourceVersionMetadataChainNode {
         uint256 resourceId;
         LedgerTime created;
@@ -52,12 +86,107 @@ contract EthrDIDLinkedResourcesRegistry {
     }
 
     function createResource(address didIdentity, string memory resourceName, string memory resourceType, string memory resourceVersion, string memory mediaType, bytes memory content) public onlyDidIdentityOwner(didIdentity) {
+        writeResource(didIdentity, resourceName, resourceType, resourceVersion, mediaType, content);
+    }
+
+    function createResourceAsDelegate(address didIdentity, bytes32 delegateType, string memory resourceName, string memory resourceType, string memory resourceVersion, string memory mediaType, bytes memory content) public onlyDidIdentityOwnerOrDelegate(didIdentity, delegateType) {
+        writeResource(didIdentity, resourceName, resourceType, resourceVersion, mediaType, content);
+    }
+
+    // writes all `resources` atomically in a single transaction, emitting a `NewResource` event for each (in order)
+    function createResources(address didIdentity, ResourceInputData[] memory resources) public onlyDidIdentityOwner(didIdentity) {
+        writeResources(didIdentity, resources);
+    }
+
+    function createResourcesAsDelegate(address didIdentity, bytes32 delegateType, ResourceInputData[] memory resources) public onlyDidIdentityOwnerOrDelegate(didIdentity, delegateType) {
+        writeResources(didIdentity, resources);
+    }
+
+    // appends a tombstone version to the resource, signalling that it should no longer be used.
+    // Writing a new version of the resource afterwards reactivates it.
+    function deactivateResource(address didIdentity, string memory resourceName, string memory resourceType) public onlyDidIdentityOwner(didIdentity) {
+        writeTombstone(didIdentity, resourceName, resourceType);
+    }
+
+    function deactivateResourceAsDelegate(address didIdentity, bytes32 delegateType, string memory resourceName, string memory resourceType) public onlyDidIdentityOwnerOrDelegate(didIdentity, delegateType) {
+        writeTombstone(didIdentity, resourceName, resourceType);
+    }
+
+    function isResourceDeactivated(address didIdentity, bytes32 resourceKey) public view returns (bool) {
+        return deactivatedResources[didIdentity][resourceKey];
+    }
+
+    // the key of a resource (by name & type) within the resources of a DID identity
+    function getResourceKey(string memory resourceName, string memory resourceType) public pure returns (bytes32) {
+        return keccak256(resourceKeyPreimage(resourceName, resourceType));
+    }
+
+    function resourceKeyPreimage(string memory resourceName, string memory resourceType) private pure returns (bytes memory) {
+        return abi.encode(resourceName, resourceType);
+    }
+
+    // permits a relayer to submit a resource on behalf of the identity owner, who signs the resource offline.
+    // The signature is over `createResourceSignedHash`, using the identity owner's current `nonce`.
+    function createResourceSigned(address didIdentity, uint8 sigV, bytes32 sigR, bytes32 sigS, string memory resourceName, string memory resourceType, string memory resourceVersion, string memory mediaType, bytes memory content) public {
+        bytes32 hash = createResourceSignedHash(didIdentity, resourceName, resourceType, resourceVersion, mediaType, content);
+        checkSignature(didIdentity, sigV, sigR, sigS, hash);
+        writeResource(didIdentity, resourceName, resourceType, resourceVersion, mediaType, content);
+    }
+
+    // registry-style (EthereumDIDRegistry) hash of a resource to be signed by the identity owner.
+    // dynamic fields are hashed individually, so that their packed encoding is unambiguous.
+    function createResourceSignedHash(address didIdentity, string memory resourceName, string memory resourceType, string memory resourceVersion, string memory mediaType, bytes memory content) public view returns (bytes32) {
+        return keccak256(abi.encodePacked(
+            bytes1(0x19),
+            bytes1(0),
+            this,
+            nonce[didRegistry.identityOwner(didIdentity)],
+            didIdentity,
+            "createResource",
+            keccak256(bytes(resourceName)),
+            keccak256(bytes(resourceType)),
+            keccak256(bytes(resourceVersion)),
+            keccak256(bytes(mediaType)),
+            keccak256(content)
+        ));
+    }
+
+    function checkSignature(address identity, uint8 sigV, bytes32 sigR, bytes32 sigS, bytes32 hash) internal returns (address) {
+        address signer = ecrecover(hash, sigV, sigR, sigS);
+        require(signer == didRegistry.identityOwner(identity), "bad_signature");
+        nonce[signer]++;
+        return signer;
+    }
+
+    function writeResources(address didIdentity, ResourceInputData[] memory resources) private {
+        for (uint256 i = 0; i < resources.length; i++) {
+            ResourceInputData memory input = resources[i];
+            writeResource(didIdentity, input.resourceName, input.resourceType, input.resourceVersion, input.mediaType, input.content);
+        }
+    }
+
+    function writeTombstone(address didIdentity, string memory resourceName, string memory resourceType) private {
+        bytes32 key = getResourceKey(resourceName, resourceType);
+        require(resourceMetadataChains[didIdentity][key].length > 0, "not_found");
+        require(!deactivatedResources[didIdentity][key], "already_deactivated");
+
+        uint256 resourceId = writeResource(didIdentity, resourceName, resourceType, "", TOMBSTONE_MEDIA_TYPE, "");
+        deactivatedResources[didIdentity][key] = true;
+
+        emit ResourceDeactivated(didIdentity, resourceId, string(resourceKeyPreimage(resourceName, resourceType)));
+    }
+
+    function writeResource(address didIdentity, string memory resourceName, string memory resourceType, string memory resourceVersion, string memory mediaType, bytes memory content) private returns (uint256) {
         globalResourceCounter++;
 
         uint256 resourceId = globalResourceCounter;
-        string memory resourceNameAndType = string(abi.encodePacked(resourceName, resourceType));
+        bytes32 key = getResourceKey(resourceName, resourceType);
 
-        uint256 metadataChainNodeIndex = updateMetadataChain(didIdentity, resourceNameAndType, resourceId);
+        if (deactivatedResources[didIdentity][key]) {
+            delete deactivatedResources[didIdentity][key];
+        }
+
+        uint256 metadataChainNodeIndex = updateMetadataChain(didIdentity, key, resourceId);
 
         LedgerTime memory createdLedgerTime = LedgerTime({
             blockTimestamp: uint40(block.timestamp),
@@ -76,22 +205,25 @@ contract EthrDIDLinkedResourcesRegistry {
             }),
             content: content
         });
-        emit NewResource(didIdentity, resourceId, resourceNameAndType, resource);
+        emit NewResource(didIdentity, resourceId, string(resourceKeyPreimage(resourceName, resourceType)), resource);
+
+        return resourceId;
     }
 
-    function updateMetadataChain(address didIdentity, string memory resourceNameAndType, uint256 resourceId) private returns (uint256) {
+    function updateMetadataChain(address didIdentity, bytes32 resourceKey, uint256 resourceId) private returns (uint256) {
         // update the previous resource version metadata chain node (if it exists)
         uint256 previousResourceId = 0;
-        uint256 resourceMetadataChainLength = resourceMetadataChains[didIdentity][resourceNameAndType].length;
+        uint256 resourceMetadataChainLength = resourceMetadataChains[didIdentity][resourceKey].length;
 
         if (resourceMetadataChainLength > 0) {
             // set
-            resourceMetadataChains[didIdentity][resourceNameAndType][resourceMetadataChainLength - 1].nextResourceId = resourceId;
+            resourceMetadataChains[didIdentity][resourceKey][resourceMetadataChainLength - 1].nextResourceId = resourceId;
             // remember
-            previousResourceId = resourceMetadataChains[didIdentity][resourceNameAndType][resourceMetadataChainLength - 1].resourceId;
+            previousResourceId = resourceMetadataChains[didIdentity][resourceKey][resourceMetadataChainLength - 1].resourceId;
         }
 
-        // TODO - assert new ledger time is greater than previous ledger time
+        // block timestamps never decrease, so the chain is ordered by ledger time (versions written in the same block share one),
+        // which clients rely on to find the version at a given time
 
         // create the new (current) resource version metadata chain node
         ResourceVersionMetadataChainNode memory newResourceVersionMetadataChainNode = ResourceVersionMetadataChainNode({
@@ -103,34 +235,34 @@ contract EthrDIDLinkedResourcesRegistry {
             nextResourceId: 0,
             previousResourceId: previousResourceId
         });
-        resourceMetadataChains[didIdentity][resourceNameAndType].push(newResourceVersionMetadataChainNode);
+        resourceMetadataChains[didIdentity][resourceKey].push(newResourceVersionMetadataChainNode);
 
         // index is length pre push
         return resourceMetadataChainLength;
     }
 
-    function getResourceMetadataChain(address didIdentity, string memory resourceNameAndType) public view returns (ResourceVersionMetadataChainNode[] memory) {
-        return resourceMetadataChains[didIdentity][resourceNameAndType];
+    function getResourceMetadataChain(address didIdentity, bytes32 resourceKey) public view returns (ResourceVersionMetadataChainNode[] memory) {
+        return resourceMetadataChains[didIdentity][resourceKey];
     }
 
-    function getResourceMetadataChainLength(address didIdentity, string memory resourceNameAndType) public view returns (uint256) {
-        return resourceMetadataChains[didIdentity][resourceNameAndType].length;
+    function getResourceMetadataChainLength(address didIdentity, bytes32 resourceKey) public view returns (uint256) {
+        return resourceMetadataChains[didIdentity][resourceKey].length;
     }
 
-    function getResourceMetadataChainNode(address didIdentity, string memory resourceNameAndType, uint256 index) public view returns (ResourceVersionMetadataChainNode memory) {
-        return resourceMetadataChains[didIdentity][resourceNameAndType][index];
+    function getResourceMetadataChainNode(address didIdentity, bytes32 resourceKey, uint256 index) public view returns (ResourceVersionMetadataChainNode memory) {
+        return resourceMetadataChains[didIdentity][resourceKey][index];
     }
 
-    function getResourceMetadataChainLatestNode(address didIdentity, string memory resourceNameAndType) public view returns (ResourceVersionMetadataChainNode memory) {
-        uint256 resourceMetadataChainLength = resourceMetadataChains[didIdentity][resourceNameAndType].length;
-        return resourceMetadataChains[didIdentity][resourceNameAndType][resourceMetadataChainLength - 1];
+    function getResourceMetadataChainLatestNode(address didIdentity, bytes32 resourceKey) public view returns (ResourceVersionMetadataChainNode memory) {
+        uint256 resourceMetadataChainLength = resourceMetadataChains[didIdentity][resourceKey].length;
+        return resourceMetadataChains[didIdentity][resourceKey][resourceMetadataChainLength - 1];
     }
 
-    function getResourceMetadataChainSlice(address didIdentity, string memory resourceNameAndType, uint256 start, uint256 end) public view returns (ResourceVersionMetadataChainNode[] memory) {
+    function getResourceMetadataChainSlice(address didIdentity, bytes32 resourceKey, uint256 start, uint256 end) public view returns (ResourceVersionMetadataChainNode[] memory) {
         ResourceVersionMetadataChainNode[] memory resourceMetadataChainSlice = new ResourceVersionMetadataChainNode[](end - start);
 
         for (uint256 i = start; i < end; i++) {
-            resourceMetadataChainSlice[i - start] = resourceMetadataChains[didIdentity][resourceNameAndType][i];
+            resourceMetadataChainSlice[i - start] = resourceMetadataChains[didIdentity][resourceKey][i];
         }
 
         return resourceMetadataChainSlice;